@@ -4,7 +4,7 @@ use smithay::{
     backend::{
         renderer::{
             damage::OutputDamageTracker,
-            element::{surface::WaylandSurfaceRenderElement, AsRenderElements},
+            element::AsRenderElements,
             gles::GlesRenderer,
         },
         winit::{self, WinitEvent, WinitEventLoop, WinitGraphicsBackend, WinitError},
@@ -34,6 +34,7 @@ impl Backend for WinitData {
     }
 }
 use crate::state::{Backend, CalloopData, CompState, CONFIG};
+use crate::utils::render::RenderElements;
 
 pub fn init_winit() {
     //event loop for the compositor
@@ -62,7 +63,7 @@ pub fn init_winit() {
     
     output.change_current_state(
         Some(mode),
-        Some(Transform::Flipped180),
+        Some(Transform::Normal),
         None,
         Some((0, 0).into()),
     );
@@ -88,10 +89,7 @@ pub fn init_winit() {
 
     let state = &mut data.state;
 
-    // map output to every workspace
-    for workspace in state.workspaces.iter() {
-        workspace.add_output(output.clone());
-    }
+    state.add_output(output.clone());
 
     std::env::set_var("WAYLAND_DISPLAY", &state.socket_name);
 
@@ -144,6 +142,10 @@ pub fn winit_dispatch(
                 None,
                 None,
             );
+            state.workspaces.retile(output);
+            state
+                .output_management_state
+                .update::<WinitData>(&state.dh, &state.workspaces);
         }
         WinitEvent::Input(event) => state.process_input_event(event),
         _ => (),
@@ -165,16 +167,37 @@ pub fn winit_dispatch(
 
     winitdata.backend.bind().unwrap();
 
-    let mut renderelements: Vec<WaylandSurfaceRenderElement<_>> = vec![];
+    let mut renderelements: Vec<RenderElements<GlesRenderer>> = vec![];
 
-    let workspace = state.workspaces.current_mut();
-    let output = workspace.outputs().next().unwrap();
+    let Some(workspace) = state.workspaces.shown_on(output) else {
+        display.flush_clients().unwrap();
+        return;
+    };
+    let scale = output.current_scale().fractional_scale();
     let layer_map = layer_map_for_output(output);
     let (lower, upper): (Vec<&LayerSurface>, Vec<&LayerSurface>) = layer_map
         .layers()
         .rev()
         .partition(|s| matches!(s.layer(), Layer::Background | Layer::Bottom));
 
+    // the tablet tool cursor and the window switcher are drawn above everything else
+    if let Some(output_geo) = state.workspaces.output_geometry(output) {
+        renderelements.extend(state.tablet_cursor.render_elements::<_, RenderElements<_>>(
+            winitdata.backend.renderer(),
+            output_geo,
+            scale,
+        ));
+    }
+    if let (Some(switcher), Some(output_geo)) =
+        (&state.switcher, state.workspaces.output_geometry(output))
+    {
+        renderelements.extend(switcher.render_elements::<_, RenderElements<_>>(
+            winitdata.backend.renderer(),
+            output_geo,
+            scale,
+        ));
+    }
+
     renderelements.extend(
         upper
             .into_iter()
@@ -184,16 +207,35 @@ pub fn winit_dispatch(
                     .map(|geo| (geo.loc, surface))
             })
             .flat_map(|(loc, surface)| {
-                AsRenderElements::<GlesRenderer>::render_elements::<WaylandSurfaceRenderElement<_>>(
+                AsRenderElements::<GlesRenderer>::render_elements::<RenderElements<GlesRenderer>>(
                     surface,
                     winitdata.backend.renderer(),
-                    loc.to_physical_precise_round(1),
-                    Scale::from(1.0)
+                    loc.to_physical_precise_round(scale),
+                    Scale::from(scale)
                 )
             }),
     );
 
-    renderelements.extend(workspace.render_elements(winitdata.backend.renderer()));
+    // during a workspace swipe the neighbour in the direction of the swipe slides in
+    let offset = state.workspace_swipe_offset();
+    renderelements.extend(
+        workspace.render_elements(winitdata.backend.renderer(), (offset, 0).into()),
+    );
+    if let (Some(neighbour), Some(output_geo)) = (
+        state
+            .workspaces
+            .neighbour(offset < 0)
+            .filter(|_| offset != 0)
+            .and_then(|name| state.workspaces.get(&name))
+            .filter(|w| w.outputs().next() == Some(output)),
+        state.workspaces.output_geometry(output),
+    ) {
+        let width = output_geo.size.w;
+        let offset = if offset < 0 { offset + width } else { offset - width };
+        renderelements.extend(
+            neighbour.render_elements(winitdata.backend.renderer(), (offset, 0).into()),
+        );
+    }
 
     renderelements.extend(
         lower
@@ -204,11 +246,11 @@ pub fn winit_dispatch(
                     .map(|geo| (geo.loc, surface))
             })
             .flat_map(|(loc, surface)| {
-                AsRenderElements::<GlesRenderer>::render_elements::<WaylandSurfaceRenderElement<_>>(
+                AsRenderElements::<GlesRenderer>::render_elements::<RenderElements<GlesRenderer>>(
                     surface,
                     winitdata.backend.renderer(),
-                    loc.to_physical_precise_round(1),
-                    Scale::from(1.0)
+                    loc.to_physical_precise_round(scale),
+                    Scale::from(scale)
                 )
             }),
     );
@@ -1,19 +1,153 @@
-use smithay::input::keyboard::keysyms;
+use smithay::{
+    input::keyboard::{keysyms, xkb, KeysymHandle, ModifiersState, XkbConfig},
+    output::{Output, Scale},
+    utils::Transform,
+};
 use crate::config::types::KeyModifiersDef;
 use indexmap::IndexMap;
+use regex::Regex;
+use std::path::PathBuf;
 mod types;
 
 #[derive(Debug)]
 pub struct Config {
+    /// Amount of persistent workspaces, named "1" to "n". Other workspaces
+    /// are created when switched to and destroyed once empty and hidden.
     pub workspaces: u8,
+    /// Bindings of the default mode.
     pub keybindings: IndexMap<KeyPattern, Action>,
+    /// Bindings of the modes entered with `Action::EnterMode`, keyed by mode name.
+    /// Escape returns to the default mode from any of them.
+    pub modes: IndexMap<String, IndexMap<KeyPattern, Action>>,
+    /// Multi-key sequences of the default mode, every key pressed within
+    /// `chord_timeout` of the previous one. Take precedence over `keybindings`.
+    pub chords: IndexMap<Vec<KeyPattern>, Action>,
+    /// Milliseconds a started chord waits for its next key.
+    pub chord_timeout: u64,
+    /// Bindings of the default mode run when their key is released without
+    /// another key pressed in between, so tapping a modifier can be bound.
+    pub release_bindings: IndexMap<KeyPattern, Action>,
+    /// Bindings of the default mode repeated at the keyboard repeat rate while held.
+    pub repeat_bindings: IndexMap<KeyPattern, Action>,
+    /// Pointer button and scroll wheel bindings, the event doesn't reach the client.
+    pub mouse_bindings: IndexMap<MousePattern, Action>,
+    /// Like `mouse_bindings`, only while the pointer is over no window.
+    pub background_bindings: IndexMap<MousePattern, Action>,
+    /// Fingers of the horizontal touchpad swipe that drags the shown workspace
+    /// aside to switch to its neighbour, 0 disables it.
+    pub workspace_swipe_fingers: u32,
+    /// Touchpad gestures run when they end, gestures with a finger count that is
+    /// bound to nothing go to the client under the pointer.
+    pub gesture_bindings: IndexMap<GesturePattern, Action>,
     pub gaps: (i32, i32),
+    /// Drop the gaps while only one tiled window is visible on a workspace.
+    pub smart_gaps: bool,
+    /// Whether `WorkspaceNext` and `WorkspacePrev` wrap around at the last and first workspace.
+    pub workspace_wrap: bool,
+    /// Outputs workspaces are created on and return to once connected,
+    /// keyed by workspace name.
+    pub workspace_outputs: IndexMap<String, String>,
+    /// Gaps of specific workspaces, keyed by workspace name.
+    pub workspace_gaps: IndexMap<String, (i32, i32)>,
+    /// Gaps of workspaces shown on specific outputs, keyed by output name.
+    pub output_gaps: IndexMap<String, (i32, i32)>,
     pub autostart: Vec<String>,
     pub tile_ratio_update_interval: f32,
+    pub default_layout: LayoutKind,
+    pub master_count: usize,
+    pub master_factor: f32,
+    /// Width presets of the scrolling layout's columns, as fractions of the output width.
+    pub column_widths: Vec<f32>,
+    /// Index into `column_widths` used for new columns.
+    pub default_column_width: usize,
+    /// Height of a tab header of tabbed and stacked containers.
+    pub tab_height: i32,
+    pub active_tab_color: [f32; 4],
+    pub inactive_tab_color: [f32; 4],
+    /// Width of the border drawn around windows, 0 disables borders.
+    pub border_width: i32,
+    pub active_border_color: [f32; 4],
+    pub inactive_border_color: [f32; 4],
+    /// Where `Action::SaveLayout` and `Action::LoadLayout` keep their files.
+    pub layout_dir: PathBuf,
+    /// Save the layout of every workspace on quit and restore it on startup.
+    pub persist_layouts: bool,
+    /// Evaluated in order on the initial commit of every new toplevel.
+    pub window_rules: Vec<WindowRule>,
+    pub keyboard: KeyboardConfig,
+    /// Settings of input devices, keyed by device name or by `type:pointer`,
+    /// `type:touch` or `type:tablet`. The first matching entry setting a field is used.
+    /// There is no libinput backend yet, so acceleration, tapping and the like can't be set.
+    pub devices: IndexMap<String, DeviceConfig>,
+    /// How the pointer moves the keyboard focus.
+    pub focus_policy: FocusPolicy,
+    /// Milliseconds the pointer has to rest on a window before it is focused
+    /// by `FocusPolicy::FollowsMouse` and `FocusPolicy::Sloppy`, 0 focuses right away.
+    pub focus_delay: u64,
+    /// Give new windows the keyboard focus when they are mapped.
+    pub focus_new_windows: bool,
+    /// Scratchpads toggled with `Action::ToggleScratchpad(Some(name))`, keyed by name.
+    pub scratchpads: IndexMap<String, NamedScratchpad>,
+    /// Settings of specific outputs, keyed by connector name, make or model.
+    pub outputs: IndexMap<String, OutputConfig>,
 }
 
-#[derive(Debug, Clone)]
-pub struct OutputConfig((i32, i32), Option<u32>);
+impl Config {
+    /// The output set for a device with the given name and type, see `Config::devices`.
+    pub fn device_output(&self, name: &str, kind: &str) -> Option<&str> {
+        self.devices
+            .iter()
+            .filter(|(key, _)| *key == name || key.strip_prefix("type:") == Some(kind))
+            .find_map(|(_, config)| config.map_to_output.as_deref())
+    }
+
+    pub fn output_config(&self, output: &Output) -> Option<&OutputConfig> {
+        let properties = output.physical_properties();
+        [output.name(), properties.make, properties.model]
+            .iter()
+            .find_map(|key| self.outputs.get(key))
+    }
+}
+
+/// Applied to an output when it is connected, unset fields keep the
+/// state the backend gave the output.
+#[derive(Debug, Clone, Default)]
+pub struct OutputConfig {
+    /// Top left corner of the output in the global compositor space.
+    pub position: Option<(i32, i32)>,
+    pub scale: Option<f64>,
+    pub transform: Option<Transform>,
+    /// Preferred mode as size in pixels and refresh rate in mHz,
+    /// any refresh rate is accepted if it is left out.
+    pub mode: Option<((i32, i32), Option<u32>)>,
+}
+
+impl OutputConfig {
+    pub fn apply(&self, output: &Output) {
+        // only modes the output advertises can be used
+        let mode = self.mode.and_then(|(size, refresh)| {
+            output.modes().into_iter().find(|mode| {
+                mode.size == size.into() && refresh.is_none_or(|r| mode.refresh == r as i32)
+            })
+        });
+        let scale = self.scale.map(|scale| {
+            if scale.fract() == 0.0 {
+                Scale::Integer(scale as i32)
+            } else {
+                Scale::Fractional(scale)
+            }
+        });
+        output.change_current_state(
+            mode,
+            self.transform,
+            scale,
+            self.position.map(Into::into),
+        );
+        if let Some(mode) = mode {
+            output.set_preferred(mode);
+        }
+    }
+}
 
 pub fn generate_config() -> Config {
     let mut keybinding_map = indexmap::IndexMap::<KeyPattern, Action>::new();
@@ -44,51 +178,395 @@ pub fn generate_config() -> Config {
     keybinding_map.insert(
         KeyPattern {
             modifiers: KeyModifiersDef(vec![KeyModifier::Ctrl]).into(),
-            key: keysyms::KEY_a,
+            key: keysyms::KEY_1,
         },
-        Action::DecreaseTileRatio,
+        Action::Workspace(String::from("1")),
     );
 
     keybinding_map.insert(
         KeyPattern {
             modifiers: KeyModifiersDef(vec![KeyModifier::Ctrl]).into(),
-            key: keysyms::KEY_d,
+            key: keysyms::KEY_2,
         },
-        Action::IncreaseTileRatio,
+        Action::Workspace(String::from("2")),
     );
 
     keybinding_map.insert(
         KeyPattern {
             modifiers: KeyModifiersDef(vec![KeyModifier::Ctrl]).into(),
-            key: keysyms::KEY_1,
+            key: keysyms::KEY_3,
         },
-        Action::Workspace(0),
+        Action::Workspace(String::from("3")),
     );
 
     keybinding_map.insert(
         KeyPattern {
-            modifiers: KeyModifiersDef(vec![KeyModifier::Ctrl]).into(),
-            key: keysyms::KEY_2,
+            modifiers: KeyModifiersDef(vec![KeyModifier::Super]).into(),
+            key: keysyms::KEY_space,
         },
-        Action::Workspace(1),
+        Action::CycleLayout,
     );
 
     keybinding_map.insert(
         KeyPattern {
-            modifiers: KeyModifiersDef(vec![KeyModifier::Ctrl]).into(),
-            key: keysyms::KEY_3,
+            modifiers: KeyModifiersDef(vec![KeyModifier::Super]).into(),
+            key: keysyms::KEY_j,
+        },
+        Action::FocusNext,
+    );
+
+    keybinding_map.insert(
+        KeyPattern {
+            modifiers: KeyModifiersDef(vec![KeyModifier::Super]).into(),
+            key: keysyms::KEY_k,
+        },
+        Action::FocusPrev,
+    );
+
+    keybinding_map.insert(
+        KeyPattern {
+            modifiers: KeyModifiersDef(vec![KeyModifier::Super]).into(),
+            key: keysyms::KEY_w,
+        },
+        Action::CycleColumnWidth,
+    );
+
+    keybinding_map.insert(
+        KeyPattern {
+            modifiers: KeyModifiersDef(vec![KeyModifier::Super, KeyModifier::Shift]).into(),
+            key: keysyms::KEY_h,
+        },
+        Action::MoveWindowLeft,
+    );
+
+    keybinding_map.insert(
+        KeyPattern {
+            modifiers: KeyModifiersDef(vec![KeyModifier::Super, KeyModifier::Shift]).into(),
+            key: keysyms::KEY_l,
+        },
+        Action::MoveWindowRight,
+    );
+
+    keybinding_map.insert(
+        KeyPattern {
+            modifiers: KeyModifiersDef(vec![KeyModifier::Super]).into(),
+            key: keysyms::KEY_t,
+        },
+        Action::LayoutTabbed,
+    );
+
+    keybinding_map.insert(
+        KeyPattern {
+            modifiers: KeyModifiersDef(vec![KeyModifier::Super]).into(),
+            key: keysyms::KEY_s,
+        },
+        Action::LayoutStacked,
+    );
+
+    keybinding_map.insert(
+        KeyPattern {
+            modifiers: KeyModifiersDef(vec![KeyModifier::Super]).into(),
+            key: keysyms::KEY_e,
+        },
+        Action::LayoutSplit,
+    );
+
+    keybinding_map.insert(
+        KeyPattern {
+            modifiers: KeyModifiersDef(vec![KeyModifier::Super, KeyModifier::Shift]).into(),
+            key: keysyms::KEY_j,
+        },
+        Action::NextTab,
+    );
+
+    keybinding_map.insert(
+        KeyPattern {
+            modifiers: KeyModifiersDef(vec![KeyModifier::Super, KeyModifier::Shift]).into(),
+            key: keysyms::KEY_k,
+        },
+        Action::PrevTab,
+    );
+
+    keybinding_map.insert(
+        KeyPattern {
+            modifiers: KeyModifiersDef(vec![KeyModifier::Super, KeyModifier::Shift]).into(),
+            key: keysyms::KEY_equal,
+        },
+        Action::AdjustGaps(GapKind::Inner, 5),
+    );
+
+    keybinding_map.insert(
+        KeyPattern {
+            modifiers: KeyModifiersDef(vec![KeyModifier::Super, KeyModifier::Shift]).into(),
+            key: keysyms::KEY_minus,
+        },
+        Action::AdjustGaps(GapKind::Inner, -5),
+    );
+
+    keybinding_map.insert(
+        KeyPattern {
+            modifiers: KeyModifiersDef(vec![KeyModifier::Super]).into(),
+            key: keysyms::KEY_bracketright,
+        },
+        Action::WorkspaceNext,
+    );
+
+    keybinding_map.insert(
+        KeyPattern {
+            modifiers: KeyModifiersDef(vec![KeyModifier::Super]).into(),
+            key: keysyms::KEY_bracketleft,
+        },
+        Action::WorkspacePrev,
+    );
+
+    keybinding_map.insert(
+        KeyPattern {
+            modifiers: KeyModifiersDef(vec![KeyModifier::Super]).into(),
+            key: keysyms::KEY_grave,
+        },
+        Action::WorkspaceBackAndForth,
+    );
+
+    keybinding_map.insert(
+        KeyPattern {
+            modifiers: KeyModifiersDef(vec![KeyModifier::Super, KeyModifier::Shift]).into(),
+            key: keysyms::KEY_bracketright,
+        },
+        Action::MoveWindowNext,
+    );
+
+    keybinding_map.insert(
+        KeyPattern {
+            modifiers: KeyModifiersDef(vec![KeyModifier::Super, KeyModifier::Shift]).into(),
+            key: keysyms::KEY_bracketleft,
+        },
+        Action::MoveWindowPrev,
+    );
+
+    keybinding_map.insert(
+        KeyPattern {
+            modifiers: KeyModifiersDef(vec![KeyModifier::Super]).into(),
+            key: keysyms::KEY_minus,
+        },
+        Action::ToggleScratchpad(None),
+    );
+
+    keybinding_map.insert(
+        KeyPattern {
+            modifiers: KeyModifiersDef(vec![KeyModifier::Super, KeyModifier::Shift]).into(),
+            key: keysyms::KEY_grave,
+        },
+        Action::MoveToScratchpad,
+    );
+
+    keybinding_map.insert(
+        KeyPattern {
+            modifiers: KeyModifiersDef(vec![KeyModifier::Super]).into(),
+            key: keysyms::KEY_y,
+        },
+        Action::ToggleSticky,
+    );
+
+    keybinding_map.insert(
+        KeyPattern {
+            modifiers: KeyModifiersDef(vec![KeyModifier::Alt]).into(),
+            key: keysyms::KEY_Tab,
+        },
+        Action::SwitcherNext,
+    );
+
+    keybinding_map.insert(
+        KeyPattern {
+            modifiers: KeyModifiersDef(vec![KeyModifier::Alt, KeyModifier::Shift]).into(),
+            key: keysyms::KEY_Tab,
+        },
+        Action::SwitcherPrev,
+    );
+
+    keybinding_map.insert(
+        KeyPattern {
+            modifiers: KeyModifiersDef(vec![KeyModifier::Alt]).into(),
+            key: keysyms::KEY_grave,
+        },
+        Action::FocusLast,
+    );
+
+    keybinding_map.insert(
+        KeyPattern {
+            modifiers: KeyModifiersDef(vec![KeyModifier::Super]).into(),
+            key: keysyms::KEY_r,
+        },
+        Action::EnterMode(String::from("resize")),
+    );
+
+    let mut resize_map = IndexMap::<KeyPattern, Action>::new();
+    for (key, action) in [
+        (keysyms::KEY_h, Action::DecreaseTileRatio),
+        (keysyms::KEY_l, Action::IncreaseTileRatio),
+        (keysyms::KEY_j, Action::DecreaseMasterCount),
+        (keysyms::KEY_k, Action::IncreaseMasterCount),
+        (keysyms::KEY_Return, Action::EnterMode(String::from(DEFAULT_MODE))),
+    ] {
+        resize_map.insert(
+            KeyPattern {
+                modifiers: KeyModifiersDef(vec![]).into(),
+                key,
+            },
+            action,
+        );
+    }
+    let mut modes = IndexMap::new();
+    modes.insert(String::from("resize"), resize_map);
+
+    let mut chords = IndexMap::new();
+    chords.insert(
+        vec![
+            KeyPattern::parse("Super+x").unwrap(),
+            KeyPattern::parse("t").unwrap(),
+        ],
+        Action::LayoutTabbed,
+    );
+    chords.insert(
+        vec![
+            KeyPattern::parse("Super+x").unwrap(),
+            KeyPattern::parse("s").unwrap(),
+        ],
+        Action::LayoutStacked,
+    );
+
+    // pressing Super_L already sets the logo modifier
+    let mut release_bindings = IndexMap::new();
+    release_bindings.insert(
+        KeyPattern::parse("Super+Super_L").unwrap(),
+        Action::Spawn(String::from("fuzzel")),
+    );
+
+    let mut repeat_bindings = IndexMap::new();
+    repeat_bindings.insert(
+        KeyPattern::parse("Ctrl+a").unwrap(),
+        Action::DecreaseTileRatio,
+    );
+    repeat_bindings.insert(
+        KeyPattern::parse("Ctrl+d").unwrap(),
+        Action::IncreaseTileRatio,
+    );
+
+    let mut mouse_bindings = IndexMap::new();
+    mouse_bindings.insert(
+        MousePattern::parse("Super+Button1").unwrap(),
+        Action::MoveWithPointer,
+    );
+    mouse_bindings.insert(
+        MousePattern::parse("Super+ScrollUp").unwrap(),
+        Action::WorkspaceNext,
+    );
+    mouse_bindings.insert(
+        MousePattern::parse("Super+ScrollDown").unwrap(),
+        Action::WorkspacePrev,
+    );
+
+    let mut background_bindings = IndexMap::new();
+    background_bindings.insert(
+        MousePattern::parse("ScrollUp").unwrap(),
+        Action::WorkspaceNext,
+    );
+    background_bindings.insert(
+        MousePattern::parse("ScrollDown").unwrap(),
+        Action::WorkspacePrev,
+    );
+    background_bindings.insert(
+        MousePattern::parse("Button3").unwrap(),
+        Action::Spawn(String::from("fuzzel")),
+    );
+
+    let mut gesture_bindings = IndexMap::new();
+    gesture_bindings.insert(
+        GesturePattern {
+            fingers: 4,
+            kind: GestureKind::PinchIn,
+        },
+        Action::SetLayout(LayoutKind::Grid),
+    );
+    gesture_bindings.insert(
+        GesturePattern {
+            fingers: 4,
+            kind: GestureKind::PinchOut,
+        },
+        Action::SetLayout(LayoutKind::Bsp),
+    );
+    gesture_bindings.insert(
+        GesturePattern {
+            fingers: 4,
+            kind: GestureKind::SwipeDown,
         },
-        Action::Workspace(2),
+        Action::ToggleScratchpad(None),
     );
 
+    let layout_dir = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .unwrap_or_default()
+        .join("rustwm/layouts");
+
     let gaps = (5,5);
     let ratio_interval = 0.05;
     let cfg = Config {
         workspaces: 3,
         keybindings: keybinding_map,
+        modes,
+        chords,
+        chord_timeout: 1000,
+        release_bindings,
+        repeat_bindings,
+        mouse_bindings,
+        background_bindings,
+        workspace_swipe_fingers: 3,
+        gesture_bindings,
         gaps,
+        smart_gaps: true,
+        workspace_wrap: true,
+        workspace_outputs: IndexMap::new(),
+        workspace_gaps: IndexMap::new(),
+        output_gaps: IndexMap::new(),
         autostart: vec![],
-        tile_ratio_update_interval: ratio_interval
+        tile_ratio_update_interval: ratio_interval,
+        default_layout: LayoutKind::Bsp,
+        master_count: 1,
+        master_factor: 0.55,
+        column_widths: vec![1.0 / 3.0, 0.5, 2.0 / 3.0],
+        default_column_width: 1,
+        tab_height: 20,
+        active_tab_color: [0.28, 0.45, 0.7, 1.0],
+        inactive_tab_color: [0.2, 0.2, 0.2, 1.0],
+        border_width: 2,
+        active_border_color: [0.28, 0.45, 0.7, 1.0],
+        inactive_border_color: [0.2, 0.2, 0.2, 1.0],
+        layout_dir,
+        persist_layouts: false,
+        window_rules: vec![WindowRule {
+            app_id: Some(Regex::new("^pavucontrol$").unwrap()),
+            floating: Some(Floating {
+                size: Some((800, 600)),
+                position: None,
+            }),
+            ..WindowRule::default()
+        }],
+        keyboard: KeyboardConfig {
+            rules: String::new(),
+            model: String::new(),
+            layout: String::from("us"),
+            variant: String::new(),
+            options: None,
+            repeat_delay: 200,
+            repeat_rate: 25,
+            per_window_layout: false,
+        },
+        devices: IndexMap::new(),
+        focus_policy: FocusPolicy::FollowsMouse,
+        focus_delay: 0,
+        focus_new_windows: true,
+        scratchpads: IndexMap::new(),
+        outputs: IndexMap::new(),
     };
     cfg
 }
@@ -118,14 +596,301 @@ pub struct KeyPattern {
     pub key: u32,
 }
 
+impl KeyPattern {
+    /// Parses patterns like `Super+Shift+exclam`, the last part being an xkb keysym name.
+    pub fn parse(pattern: &str) -> Option<KeyPattern> {
+        let mut parts: Vec<&str> = pattern.split('+').collect();
+        let key = xkb::keysym_from_name(parts.pop()?, xkb::KEYSYM_NO_FLAGS);
+        if key == keysyms::KEY_NoSymbol {
+            return None;
+        }
+        let modifiers = parse_modifiers(&parts)?;
+        Some(KeyPattern { modifiers, key })
+    }
+
+    /// Matches the keysym of the key either without any modifiers applied or with
+    /// them applied, so `Shift+exclam` matches as well as `Shift+1`.
+    pub fn matches(&self, modifiers: &ModifiersState, handle: &KeysymHandle<'_>) -> bool {
+        self.modifiers == *modifiers
+            && (handle.raw_syms().contains(&self.key) || handle.modified_sym() == self.key)
+    }
+}
+
+fn parse_modifiers(names: &[&str]) -> Option<KeyModifiers> {
+    let mut modifiers: KeyModifiers = KeyModifiersDef(vec![]).into();
+    for name in names {
+        modifiers += match *name {
+            "Ctrl" | "Control" => KeyModifier::Ctrl,
+            "Alt" | "Mod1" => KeyModifier::Alt,
+            "Shift" => KeyModifier::Shift,
+            "Super" | "Logo" | "Mod4" => KeyModifier::Super,
+            _ => return None,
+        };
+    }
+    Some(modifiers)
+}
+
+/// A pointer button or scroll direction with the modifiers held at the time.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MousePattern {
+    pub modifiers: KeyModifiers,
+    pub trigger: MouseTrigger,
+}
+
+impl MousePattern {
+    /// Parses patterns like `Super+Button1` or `Ctrl+ScrollUp`, buttons numbered as in X11.
+    pub fn parse(pattern: &str) -> Option<MousePattern> {
+        let mut parts: Vec<&str> = pattern.split('+').collect();
+        let trigger = match parts.pop()? {
+            "ScrollUp" => MouseTrigger::ScrollUp,
+            "ScrollDown" => MouseTrigger::ScrollDown,
+            "ScrollLeft" => MouseTrigger::ScrollLeft,
+            "ScrollRight" => MouseTrigger::ScrollRight,
+            "Button1" => MouseTrigger::Button(BTN_LEFT),
+            "Button2" => MouseTrigger::Button(BTN_MIDDLE),
+            "Button3" => MouseTrigger::Button(BTN_RIGHT),
+            "Button8" => MouseTrigger::Button(BTN_SIDE),
+            "Button9" => MouseTrigger::Button(BTN_EXTRA),
+            _ => return None,
+        };
+        let modifiers = parse_modifiers(&parts)?;
+        Some(MousePattern { modifiers, trigger })
+    }
+}
+
+// evdev button codes
+pub const BTN_LEFT: u32 = 0x110;
+const BTN_RIGHT: u32 = 0x111;
+const BTN_MIDDLE: u32 = 0x112;
+const BTN_SIDE: u32 = 0x113;
+const BTN_EXTRA: u32 = 0x114;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseTrigger {
+    /// A button by its evdev code.
+    Button(u32),
+    /// Wheel steps, touchpad scrolling always goes to the client.
+    ScrollUp,
+    ScrollDown,
+    ScrollLeft,
+    ScrollRight,
+}
+
+/// A touchpad gesture by its finger count and main direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GesturePattern {
+    pub fingers: u32,
+    pub kind: GestureKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GestureKind {
+    SwipeLeft,
+    SwipeRight,
+    SwipeUp,
+    SwipeDown,
+    /// Fingers moved towards each other.
+    PinchIn,
+    PinchOut,
+}
+
+impl GestureKind {
+    pub fn is_pinch(&self) -> bool {
+        matches!(self, GestureKind::PinchIn | GestureKind::PinchOut)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Action {
     Quit,
     Close,
-    Workspace(u8),
-    MoveWindow(u8),
-    MoveAndSwitch(u8),
+    Workspace(String),
+    MoveWindow(String),
+    MoveAndSwitch(String),
+    WorkspaceNext,
+    WorkspacePrev,
+    WorkspaceBackAndForth,
+    MoveWindowNext,
+    MoveWindowPrev,
+    MoveWindowBackAndForth,
     IncreaseTileRatio,
     DecreaseTileRatio,
+    IncreaseMasterCount,
+    DecreaseMasterCount,
+    FocusNext,
+    FocusPrev,
+    /// Focuses the previously focused window, on whatever workspace it is.
+    FocusLast,
+    /// Switches to the next of the layouts listed in `KeyboardConfig::layout`.
+    NextKeyboardLayout,
+    /// Opens the window switcher or selects the next window in it.
+    SwitcherNext,
+    SwitcherPrev,
+    SetLayout(LayoutKind),
+    CycleLayout,
+    CycleColumnWidth,
+    MoveWindowLeft,
+    MoveWindowRight,
+    LayoutTabbed,
+    LayoutStacked,
+    LayoutSplit,
+    NextTab,
+    PrevTab,
+    MoveIntoContainer,
+    MoveOutOfContainer,
+    AdjustGaps(GapKind, i32),
+    SaveLayout(String),
+    LoadLayout(String),
+    Mark(String),
+    FocusMark(String),
+    SwapWithMark(String),
+    ToggleSticky,
+    MoveToScratchpad,
+    ToggleScratchpad(Option<String>),
+    /// Activates the bindings of a mode on top of the current one, `DEFAULT_MODE` leaves all modes.
+    EnterMode(String),
+    /// Returns to the mode that was active before the current one.
+    ExitMode,
+    /// Drags the floating window under the pointer until the button is released,
+    /// only meaningful as a button binding.
+    MoveWithPointer,
     Spawn(String),
 }
+
+/// Name of the mode using `Config::keybindings`.
+pub const DEFAULT_MODE: &str = "default";
+
+/// Matches new toplevels by app_id and title and changes how they are mapped.
+/// A rule without any pattern matches every window.
+#[derive(Debug, Clone, Default)]
+pub struct WindowRule {
+    pub app_id: Option<Regex>,
+    pub title: Option<Regex>,
+    pub workspace: Option<String>,
+    pub floating: Option<Floating>,
+    pub fullscreen: bool,
+    /// Don't give keyboard focus to the window when it is mapped.
+    pub skip_focus: bool,
+    /// Don't draw a border around the window.
+    pub no_border: bool,
+    /// Opacity the window is drawn with, between 0 and 1.
+    pub opacity: Option<f32>,
+}
+
+impl WindowRule {
+    pub fn matches(&self, app_id: Option<&str>, title: Option<&str>) -> bool {
+        let matches = |pattern: &Option<Regex>, value: Option<&str>| match pattern {
+            Some(pattern) => value.is_some_and(|v| pattern.is_match(v)),
+            None => true,
+        };
+        matches(&self.app_id, app_id) && matches(&self.title, title)
+    }
+}
+
+/// A scratchpad with its own window. The command is spawned the first time
+/// it is toggled and the next window matching `app_id` becomes its window.
+#[derive(Debug, Clone)]
+pub struct NamedScratchpad {
+    pub command: String,
+    pub app_id: Regex,
+}
+
+/// Geometry of a window floated by a `WindowRule`, by default it takes
+/// half the output and is centered. `position` is relative to the output.
+#[derive(Debug, Clone, Default)]
+pub struct Floating {
+    pub size: Option<(i32, i32)>,
+    pub position: Option<(i32, i32)>,
+}
+
+/// Settings of an input device.
+#[derive(Debug, Clone, Default)]
+pub struct DeviceConfig {
+    /// Output the absolute positions of the device are mapped onto,
+    /// the output under the pointer if unset.
+    pub map_to_output: Option<String>,
+}
+
+/// xkb keymap of the keyboard, empty fields use the xkb defaults. `layout` and
+/// `variant` may list several comma separated layouts to switch between.
+#[derive(Debug, Clone)]
+pub struct KeyboardConfig {
+    pub rules: String,
+    pub model: String,
+    pub layout: String,
+    pub variant: String,
+    pub options: Option<String>,
+    /// Milliseconds before a held key starts repeating.
+    pub repeat_delay: i32,
+    /// Repeated key presses per second.
+    pub repeat_rate: i32,
+    /// Remember the active layout of every window and restore it when it is focused.
+    pub per_window_layout: bool,
+}
+
+impl KeyboardConfig {
+    pub fn layout_count(&self) -> usize {
+        self.layout.split(',').count()
+    }
+
+    pub fn layout_name(&self, index: usize) -> &str {
+        self.layout.split(',').nth(index).unwrap_or_default()
+    }
+
+    // Every layout becomes a group of the same keymap, see `CompState::set_keyboard_layout`
+    pub fn xkb_config(&self) -> XkbConfig<'_> {
+        XkbConfig {
+            rules: &self.rules,
+            model: &self.model,
+            layout: &self.layout,
+            variant: &self.variant,
+            options: self.options.clone(),
+        }
+    }
+}
+
+/// When the window under the pointer receives the keyboard focus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusPolicy {
+    /// Whenever the pointer moves over it.
+    FollowsMouse,
+    /// When the pointer enters it, moving inside the focused window or over
+    /// empty space keeps the focus where it is.
+    Sloppy,
+    /// When it is clicked.
+    ClickToFocus,
+    /// Never, the focus only changes through keybindings and new windows.
+    KeyboardOnly,
+}
+
+/// Which of the two gaps of `Config::gaps` an action changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GapKind {
+    /// Around every window.
+    Inner,
+    /// Between the windows and the edges of the output.
+    Outer,
+}
+
+/// Tiling algorithms a workspace can be switched between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutKind {
+    Bsp,
+    MasterStack,
+    Monocle,
+    Grid,
+    Scrolling,
+}
+
+impl LayoutKind {
+    /// The layout that follows `self` when cycling with `Action::CycleLayout`.
+    pub fn next(&self) -> Self {
+        match self {
+            LayoutKind::Bsp => LayoutKind::MasterStack,
+            LayoutKind::MasterStack => LayoutKind::Monocle,
+            LayoutKind::Monocle => LayoutKind::Grid,
+            LayoutKind::Grid => LayoutKind::Scrolling,
+            LayoutKind::Scrolling => LayoutKind::Bsp,
+        }
+    }
+}
@@ -1,27 +1,68 @@
 use smithay::{
+    desktop::Window,
     backend::input::{
-            self, AbsolutePositionEvent, Axis, AxisSource, Event, InputBackend, InputEvent,
+            self, AbsolutePositionEvent, Axis, AxisSource, ButtonState, Device, Event,
+            GestureBeginEvent, GestureEndEvent, GesturePinchUpdateEvent, GestureSwipeUpdateEvent,
+            DeviceCapability, InputBackend, InputEvent, ProximityState, TabletToolButtonEvent,
+            TabletToolEvent, TabletToolProximityEvent, TabletToolTipEvent, TabletToolTipState,
+            TouchEvent, TouchSlot,
             KeyState, KeyboardKeyEvent, PointerAxisEvent, PointerButtonEvent, PointerMotionEvent,
         },
     input::{
-        keyboard::FilterResult,
+        keyboard::{self, keysyms, FilterResult, KeysymHandle, ModifiersState},
         pointer::{AxisFrame, ButtonEvent, MotionEvent, RelativeMotionEvent},
     },
-    utils::{Logical, Point, SERIAL_COUNTER},
+    reexports::{
+        calloop::timer::{TimeoutAction, Timer},
+        wayland_server::protocol::wl_surface::WlSurface,
+    },
+    utils::{IsAlive, Logical, Point, Rectangle, SERIAL_COUNTER},
+    wayland::tablet_manager::{TabletDescriptor, TabletSeatTrait},
 };
+use std::time::Duration;
 
 use crate::{
-    config::Action,
+    config::{Action, FocusPolicy, GapKind, MouseTrigger, BTN_LEFT, DEFAULT_MODE},
     state::{Backend, CompState, CONFIG},
-    utils::{focus::FocusTarget, tiling::bsp_update_layout},
+    utils::{
+        binarytree::ContainerKind,
+        gesture::{Gesture, GestureTarget},
+        focus::FocusTarget,
+        layout::Layout,
+        saved_layout, status,
+        switcher::Switcher,
+        tiling::{configured_gaps, update_layout},
+        workspace::ScratchpadToggle,
+    },
 };
 
 impl<BackendData: Backend> CompState<BackendData> {
     pub fn process_input_event<I: InputBackend>(&mut self, event: InputEvent<I>) {
         match event {
+            InputEvent::DeviceAdded { device } => {
+                if device.has_capability(DeviceCapability::TabletTool) {
+                    self.seat
+                        .tablet_seat()
+                        .add_tablet::<Self>(&self.dh, &TabletDescriptor::from(&device));
+                }
+            }
+            InputEvent::DeviceRemoved { device } => {
+                if device.has_capability(DeviceCapability::TabletTool) {
+                    let tablet_seat = self.seat.tablet_seat();
+                    tablet_seat.remove_tablet(&TabletDescriptor::from(&device));
+                    // tools are shared between tablets
+                    if tablet_seat.count_tablets() == 0 {
+                        tablet_seat.clear_tools();
+                        self.tablet_cursor.location = None;
+                    }
+                }
+            }
             InputEvent::Keyboard { event, .. } => {
                 let serial = SERIAL_COUNTER.next_serial();
                 let time = Event::time_msec(&event);
+                let mut modifiers_released = false;
+                let mut released_action = None;
+                let mut active_layout = None;
 
                 if let Some(action) = self.seat.get_keyboard().unwrap().input(
                     self,
@@ -29,33 +70,102 @@ impl<BackendData: Backend> CompState<BackendData> {
                     event.state(),
                     serial,
                     time,
-                    |_, modifiers, handle| {
-                        for (binding, action) in CONFIG.keybindings.iter() {
-                            if event.state() == KeyState::Pressed
-                                && binding.modifiers == *modifiers
-                                && handle.raw_syms().contains(&binding.key)
+                    |state, modifiers, handle| {
+                        modifiers_released =
+                            !(modifiers.ctrl || modifiers.alt || modifiers.shift || modifiers.logo);
+                        // the `grp:` options of the keymap switch layouts on their own
+                        active_layout = Some(modifiers.serialized.layout_effective as usize);
+                        let keycode = event.key_code();
+                        if event.state() == KeyState::Released {
+                            if let Some((code, token)) = state.repeat_timer.take() {
+                                if code == keycode {
+                                    state.loop_handle.remove(token);
+                                } else {
+                                    state.repeat_timer = Some((code, token));
+                                }
+                            }
+                            // the release still reaches the client, which saw the press
+                            if let Some((code, action)) = state.release_binding.take() {
+                                if code == keycode {
+                                    released_action = Some(action);
+                                }
+                            }
+                            return FilterResult::Forward;
+                        }
+                        // a tap turns into a combination once another key is pressed
+                        state.release_binding = None;
+
+                        if state.modes.is_empty() {
+                            if let Some(result) = state.chord_input(modifiers, &handle) {
+                                return result;
+                            }
+                            if let Some((_, action)) = CONFIG
+                                .repeat_bindings
+                                .iter()
+                                .find(|(binding, _)| binding.matches(modifiers, &handle))
                             {
-                                return FilterResult::Intercept(action.clone());
+                                state.start_repeat(keycode, action.clone());
+                                return FilterResult::Intercept(Some(action.clone()));
                             }
+                            state.release_binding = CONFIG
+                                .release_bindings
+                                .iter()
+                                .find(|(binding, _)| binding.matches(modifiers, &handle))
+                                .map(|(_, action)| (keycode, action.clone()));
+                        }
+
+                        let keybindings = state
+                            .modes
+                            .last()
+                            .and_then(|mode| CONFIG.modes.get(mode))
+                            .unwrap_or(&CONFIG.keybindings);
+                        for (binding, action) in keybindings.iter() {
+                            if binding.matches(modifiers, &handle) {
+                                return FilterResult::Intercept(Some(action.clone()));
+                            }
+                        }
+                        if !state.modes.is_empty()
+                            && handle.raw_syms().contains(&keysyms::KEY_Escape)
+                        {
+                            return FilterResult::Intercept(Some(Action::EnterMode(
+                                DEFAULT_MODE.to_string(),
+                            )));
                         }
                         FilterResult::Forward
                     },
                 ) {
-                    self.handle_action(action);
+                    if let Some(action) = action {
+                        self.handle_action(action);
+                    }
                 };
+                if let Some(action) = released_action {
+                    self.handle_action(action);
+                }
+                if let Some(index) = active_layout.filter(|l| *l != self.keyboard_layout) {
+                    self.keyboard_layout_changed(index);
+                }
+                // the window switcher stays open while the modifiers are held
+                if modifiers_released {
+                    if let Some(switcher) = self.switcher.take() {
+                        if let Some(window) = switcher.selected().filter(|w| w.alive()) {
+                            self.focus_window(window.clone());
+                        }
+                    }
+                }
             }
             InputEvent::PointerMotion { event } => {
                 let serial = SERIAL_COUNTER.next_serial();
                 let delta = (event.delta_x(), event.delta_y()).into();
-                self.pointer_location += delta;
 
                 // clamp to screen limits
                 // this event is never generated by winit
-                self.pointer_location = self.clamp_coords(self.pointer_location);
+                self.pointer_location = self.clamp_coords(self.pointer_location + delta);
+                self.focus_pointer_output();
+                self.drag_window();
 
                 let under = self.surface_under();
 
-                self.set_input_focus_auto();
+                self.focus_on_motion();
 
                 if let Some(ptr) = self.seat.get_pointer() {
                     ptr.motion(
@@ -80,43 +190,17 @@ impl<BackendData: Backend> CompState<BackendData> {
                 }
             }
             InputEvent::PointerMotionAbsolute { event, .. } => {
-                let output = self.workspaces.current().outputs().next().unwrap().clone();
-                let output_geo = self.workspaces.current().output_geometry(&output).unwrap();
-                let pos = event.position_transformed(output_geo.size) + output_geo.loc.to_f64();
-                let serial = SERIAL_COUNTER.next_serial();
-                let pointer = self.seat.get_pointer().unwrap();
-                self.pointer_location = self.clamp_coords(pos);
-                let under = self.surface_under();
-                self.set_input_focus_auto();
-
-                pointer.motion(
-                    self,
-                    under,
-                    &MotionEvent {
-                        location: pos,
-                        serial,
-                        time: event.time_msec(),
-                    },
-                );
+                if let Some(geo) = self.absolute_output_geometry(&event.device(), "pointer") {
+                    let pos = event.position_transformed(geo.size) + geo.loc.to_f64();
+                    self.pointer_motion_to(pos, event.time_msec());
+                }
             }
             InputEvent::PointerButton { event, .. } => {
-                let pointer = self.seat.get_pointer().unwrap();
-                let serial = SERIAL_COUNTER.next_serial();
-                let button = event.button_code();
-                let button_state = event.state();
-                self.set_input_focus_auto();
-
-                pointer.button(
-                    self,
-                    &ButtonEvent {
-                        button,
-                        state: button_state,
-                        serial,
-                        time: event.time_msec(),
-                    },
-                );
+                self.pointer_button(event.button_code(), event.state(), event.time_msec());
             }
             InputEvent::PointerAxis { event, .. } => {
+                // a tap turns into a combination once the pointer is used
+                self.release_binding = None;
                 let horizontal_amount =
                     event.amount(input::Axis::Horizontal).unwrap_or_else(|| {
                         event
@@ -130,6 +214,23 @@ impl<BackendData: Backend> CompState<BackendData> {
                 let horizontal_amount_discrete = event.amount_discrete(input::Axis::Horizontal);
                 let vertical_amount_discrete = event.amount_discrete(input::Axis::Vertical);
 
+                if matches!(event.source(), AxisSource::Wheel | AxisSource::WheelTilt) {
+                    let trigger = if vertical_amount < 0.0 {
+                        Some(MouseTrigger::ScrollUp)
+                    } else if vertical_amount > 0.0 {
+                        Some(MouseTrigger::ScrollDown)
+                    } else if horizontal_amount < 0.0 {
+                        Some(MouseTrigger::ScrollLeft)
+                    } else if horizontal_amount > 0.0 {
+                        Some(MouseTrigger::ScrollRight)
+                    } else {
+                        None
+                    };
+                    if trigger.map_or(false, |trigger| self.mouse_binding(trigger)) {
+                        return;
+                    }
+                }
+
                 {
                     let mut frame = AxisFrame::new(event.time_msec()).source(event.source());
                     if horizontal_amount != 0.0 {
@@ -151,44 +252,569 @@ impl<BackendData: Backend> CompState<BackendData> {
                     self.seat.get_pointer().unwrap().axis(self, frame);
                 }
             }
+            InputEvent::TouchDown { event, .. } => {
+                if let Some(geo) = self.absolute_output_geometry(&event.device(), "touch") {
+                    let pos = event.position_transformed(geo.size) + geo.loc.to_f64();
+                    self.touch_down(event.slot(), pos, event.time_msec());
+                }
+            }
+            InputEvent::TouchMotion { event, .. } => {
+                if let Some(geo) = self.absolute_output_geometry(&event.device(), "touch") {
+                    let pos = event.position_transformed(geo.size) + geo.loc.to_f64();
+                    self.touch_motion(event.slot(), pos, event.time_msec());
+                }
+            }
+            InputEvent::TouchUp { event, .. } => self.touch_up(event.slot(), event.time_msec()),
+            InputEvent::TouchCancel { event, .. } => {
+                if self.touch_emulated.take().is_some() {
+                    self.pointer_button(BTN_LEFT, ButtonState::Released, event.time_msec());
+                }
+                self.seat.get_touch().unwrap().cancel();
+            }
+            InputEvent::TouchFrame { .. } => self.seat.get_touch().unwrap().frame(),
+            InputEvent::TabletToolAxis { event, .. } => {
+                let Some(geo) = self.absolute_output_geometry(&event.device(), "tablet") else {
+                    return;
+                };
+                let pos = event.position_transformed(geo.size) + geo.loc.to_f64();
+                self.tablet_cursor.location = Some(pos);
+                let tablet_seat = self.seat.tablet_seat();
+                let tablet = tablet_seat.get_tablet(&TabletDescriptor::from(&event.device()));
+                let tool = tablet_seat.get_tool(&event.tool());
+                if let (Some(tablet), Some(tool)) = (tablet, tool) {
+                    if event.pressure_has_changed() {
+                        tool.pressure(event.pressure());
+                    }
+                    if event.distance_has_changed() {
+                        tool.distance(event.distance());
+                    }
+                    if event.tilt_has_changed() {
+                        tool.tilt(event.tilt());
+                    }
+                    if event.slider_has_changed() {
+                        tool.slider_position(event.slider_position());
+                    }
+                    if event.rotation_has_changed() {
+                        tool.rotation(event.rotation());
+                    }
+                    if event.wheel_has_changed() {
+                        tool.wheel(event.wheel_delta(), event.wheel_delta_discrete());
+                    }
+                    let under = self
+                        .surface_at(pos)
+                        .map(|(target, loc)| (WlSurface::from(target), loc));
+                    let serial = SERIAL_COUNTER.next_serial();
+                    tool.motion(pos, under, &tablet, serial, event.time_msec());
+                }
+            }
+            InputEvent::TabletToolProximity { event, .. } => {
+                let Some(geo) = self.absolute_output_geometry(&event.device(), "tablet") else {
+                    return;
+                };
+                let pos = event.position_transformed(geo.size) + geo.loc.to_f64();
+                let tablet_seat = self.seat.tablet_seat();
+                tablet_seat.add_tool::<Self>(&self.dh, &event.tool());
+                let tablet = tablet_seat.get_tablet(&TabletDescriptor::from(&event.device()));
+                let tool = tablet_seat.get_tool(&event.tool());
+                let (Some(tablet), Some(tool)) = (tablet, tool) else {
+                    return;
+                };
+                match event.state() {
+                    ProximityState::In => {
+                        self.tablet_cursor.location = Some(pos);
+                        if let Some((target, loc)) = self.surface_at(pos) {
+                            let serial = SERIAL_COUNTER.next_serial();
+                            tool.proximity_in(
+                                pos,
+                                (WlSurface::from(target), loc),
+                                &tablet,
+                                serial,
+                                event.time_msec(),
+                            );
+                        }
+                    }
+                    ProximityState::Out => {
+                        self.tablet_cursor.location = None;
+                        tool.proximity_out(event.time_msec());
+                    }
+                }
+            }
+            InputEvent::TabletToolTip { event, .. } => {
+                let Some(tool) = self.seat.tablet_seat().get_tool(&event.tool()) else {
+                    return;
+                };
+                match event.tip_state() {
+                    TabletToolTipState::Down => {
+                        let serial = SERIAL_COUNTER.next_serial();
+                        tool.tip_down(serial, event.time_msec());
+                        // the pen focuses like a click
+                        let under = self.tablet_cursor.location.and_then(|l| self.surface_at(l));
+                        if let Some((target, _)) = under {
+                            if CONFIG.focus_policy != FocusPolicy::KeyboardOnly {
+                                self.set_input_focus(target);
+                            }
+                        }
+                    }
+                    TabletToolTipState::Up => tool.tip_up(event.time_msec()),
+                }
+            }
+            InputEvent::TabletToolButton { event, .. } => {
+                if let Some(tool) = self.seat.tablet_seat().get_tool(&event.tool()) {
+                    let serial = SERIAL_COUNTER.next_serial();
+                    tool.button(
+                        event.button(),
+                        event.button_state(),
+                        serial,
+                        event.time_msec(),
+                    );
+                }
+            }
+            InputEvent::GestureSwipeBegin { event, .. } => {
+                self.gesture_begin(false, event.fingers(), event.time_msec())
+            }
+            InputEvent::GestureSwipeUpdate { event, .. } => self.gesture_update(
+                (event.delta_x(), event.delta_y()).into(),
+                1.0,
+                0.0,
+                event.time_msec(),
+            ),
+            InputEvent::GestureSwipeEnd { event, .. } => {
+                self.gesture_end(event.cancelled(), event.time_msec())
+            }
+            InputEvent::GesturePinchBegin { event, .. } => {
+                self.gesture_begin(true, event.fingers(), event.time_msec())
+            }
+            InputEvent::GesturePinchUpdate { event, .. } => self.gesture_update(
+                (event.delta_x(), event.delta_y()).into(),
+                event.scale(),
+                event.rotation(),
+                event.time_msec(),
+            ),
+            InputEvent::GesturePinchEnd { event, .. } => {
+                self.gesture_end(event.cancelled(), event.time_msec())
+            }
             _ => {}
         }
     }
 
+    fn pointer_motion_to(&mut self, pos: Point<f64, Logical>, time: u32) {
+        let serial = SERIAL_COUNTER.next_serial();
+        let pointer = self.seat.get_pointer().unwrap();
+        self.pointer_location = self.clamp_coords(pos);
+        self.focus_pointer_output();
+        self.drag_window();
+        let under = self.surface_under();
+        self.focus_on_motion();
+
+        pointer.motion(
+            self,
+            under,
+            &MotionEvent {
+                location: pos,
+                serial,
+                time,
+            },
+        );
+    }
+
+    fn pointer_button(&mut self, button: u32, button_state: ButtonState, time: u32) {
+        let pointer = self.seat.get_pointer().unwrap();
+        let serial = SERIAL_COUNTER.next_serial();
+        if button_state == ButtonState::Pressed
+            && CONFIG.focus_policy != FocusPolicy::KeyboardOnly
+        {
+            self.focus_under_pointer();
+        }
+        if button_state == ButtonState::Pressed {
+            self.release_binding = None;
+            if self.mouse_binding(MouseTrigger::Button(button)) {
+                self.bound_button = Some(button);
+                return;
+            }
+        } else if self.bound_button == Some(button) {
+            self.bound_button = None;
+            self.pointer_drag = None;
+            return;
+        }
+
+        pointer.button(
+            self,
+            &ButtonEvent {
+                button,
+                state: button_state,
+                serial,
+                time,
+            },
+        );
+    }
+
+    // Geometry of the output the absolute positions of a device are mapped onto,
+    // as configured for it in `CONFIG.devices`, otherwise the output under the pointer
+    fn absolute_output_geometry(
+        &self,
+        device: &impl Device,
+        kind: &str,
+    ) -> Option<Rectangle<i32, Logical>> {
+        let name = device.name();
+        let output = CONFIG
+            .device_output(&name, kind)
+            .and_then(|name| self.workspaces.outputs().find(|o| o.name() == name).cloned())
+            .or_else(|| self.workspaces.output_under(self.pointer_location))
+            .or_else(|| self.workspaces.outputs().next().cloned())?;
+        self.workspaces.output_geometry(&output)
+    }
+
+    fn touch_down(&mut self, slot: TouchSlot, pos: Point<f64, Logical>, time: u32) {
+        match self.surface_at(pos) {
+            Some((target, location)) => {
+                if CONFIG.focus_policy != FocusPolicy::KeyboardOnly {
+                    self.set_input_focus(target.clone());
+                }
+                let serial = SERIAL_COUNTER.next_serial();
+                self.seat.get_touch().unwrap().down(
+                    serial,
+                    time,
+                    &WlSurface::from(target),
+                    location,
+                    slot,
+                    pos,
+                );
+            }
+            // title bars and the other compositor drawn parts only know the pointer
+            None if self.touch_emulated.is_none() => {
+                self.touch_emulated = Some(slot);
+                self.pointer_motion_to(pos, time);
+                self.pointer_button(BTN_LEFT, ButtonState::Pressed, time);
+            }
+            None => {}
+        }
+    }
+
+    fn touch_motion(&mut self, slot: TouchSlot, pos: Point<f64, Logical>, time: u32) {
+        if self.touch_emulated == Some(slot) {
+            self.pointer_motion_to(pos, time);
+        } else {
+            self.seat.get_touch().unwrap().motion(time, slot, pos);
+        }
+    }
+
+    fn touch_up(&mut self, slot: TouchSlot, time: u32) {
+        if self.touch_emulated == Some(slot) {
+            self.touch_emulated = None;
+            self.pointer_button(BTN_LEFT, ButtonState::Released, time);
+        } else {
+            let serial = SERIAL_COUNTER.next_serial();
+            self.seat.get_touch().unwrap().up(serial, time, slot);
+        }
+    }
+
+    fn gesture_begin(&mut self, pinch: bool, fingers: u32, time: u32) {
+        let gesture = Gesture::new(pinch, fingers);
+        if gesture.target == GestureTarget::Client {
+            if let Some((target, _)) = self.surface_under() {
+                let serial = SERIAL_COUNTER.next_serial();
+                self.pointer_gestures_state
+                    .begin(pinch, target.into(), serial, time, fingers);
+            }
+        }
+        self.gesture = Some(gesture);
+    }
+
+    fn gesture_update(&mut self, delta: Point<f64, Logical>, scale: f64, rotation: f64, time: u32) {
+        let Some(gesture) = &mut self.gesture else {
+            return;
+        };
+        gesture.delta += delta;
+        gesture.scale = scale;
+        if gesture.target == GestureTarget::Client {
+            self.pointer_gestures_state
+                .update(time, delta, scale, rotation);
+        }
+    }
+
+    fn gesture_end(&mut self, cancelled: bool, time: u32) {
+        let Some(gesture) = self.gesture.take() else {
+            return;
+        };
+        match gesture.target {
+            GestureTarget::Client => {
+                let serial = SERIAL_COUNTER.next_serial();
+                self.pointer_gestures_state.end(serial, time, cancelled);
+            }
+            GestureTarget::Binding if !cancelled => {
+                if let Some(action) = CONFIG.gesture_bindings.get(&gesture.pattern()) {
+                    self.handle_action(action.clone());
+                }
+            }
+            // the switch happens once the workspace is dragged a quarter of the output aside
+            GestureTarget::Workspace if !cancelled => {
+                let width = self
+                    .workspaces
+                    .current()
+                    .outputs()
+                    .next()
+                    .and_then(|o| self.workspaces.output_geometry(o))
+                    .map_or(0, |geo| geo.size.w);
+                if gesture.delta.x.abs() > width as f64 / 4.0 {
+                    self.handle_action(if gesture.delta.x < 0.0 {
+                        Action::WorkspaceNext
+                    } else {
+                        Action::WorkspacePrev
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Horizontal offset of the shown workspace while it is swiped aside
+    pub fn workspace_swipe_offset(&self) -> i32 {
+        match &self.gesture {
+            Some(gesture) if gesture.target == GestureTarget::Workspace => {
+                gesture.delta.x.round() as i32
+            }
+            _ => 0,
+        }
+    }
+
+    // Runs the mouse binding of the trigger, returns whether there was one
+    fn mouse_binding(&mut self, trigger: MouseTrigger) -> bool {
+        let modifiers = self.seat.get_keyboard().unwrap().modifier_state();
+        let background = self.window_under().is_none();
+        let action = CONFIG
+            .mouse_bindings
+            .iter()
+            .chain(CONFIG.background_bindings.iter().filter(|_| background))
+            .find(|(binding, _)| binding.trigger == trigger && binding.modifiers == modifiers)
+            .map(|(_, action)| action.clone());
+        match action {
+            Some(action) => {
+                self.handle_action(action);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Moves the window dragged with `Action::MoveWithPointer` along with the pointer
+    fn drag_window(&mut self) {
+        let Some((window, offset)) = &self.pointer_drag else {
+            return;
+        };
+        if let Some(compwindow) = self.workspaces.comp_window(window) {
+            compwindow.borrow_mut().rec.loc = (self.pointer_location - *offset).to_i32_round();
+        }
+    }
+
+    // Positions outside of every output are clamped to the output the pointer is on
     fn clamp_coords(&self, pos: Point<f64, Logical>) -> Point<f64, Logical> {
-        if self.workspaces.current().outputs().next().is_none() {
+        if self.workspaces.output_under(pos).is_some() {
             return pos;
         }
+        let Some(geo) = self
+            .workspaces
+            .output_under(self.pointer_location)
+            .or_else(|| self.workspaces.outputs().next().cloned())
+            .and_then(|o| self.workspaces.output_geometry(&o))
+        else {
+            return pos;
+        };
 
         let (pos_x, pos_y) = pos.into();
-        let (max_x, max_y) = self
-            .workspaces
-            .current()
-            .output_geometry(self.workspaces.current().outputs().next().unwrap())
-            .unwrap()
-            .size
-            .into();
-        let clamped_x = pos_x.max(0.0).min(max_x as f64);
-        let clamped_y = pos_y.max(0.0).min(max_y as f64);
+        let clamped_x = pos_x
+            .max(geo.loc.x as f64)
+            .min((geo.loc.x + geo.size.w) as f64);
+        let clamped_y = pos_y
+            .max(geo.loc.y as f64)
+            .min((geo.loc.y + geo.size.h) as f64);
         (clamped_x, clamped_y).into()
     }
 
+    // The workspace on the output under the pointer becomes the current one
+    fn focus_pointer_output(&mut self) {
+        if let Some(output) = self.workspaces.output_under(self.pointer_location) {
+            self.workspaces.focus_output(&output);
+        }
+    }
+
+    // Moves the pointer to the middle of the current output if it is on another one
+    fn warp_to_current_output(&mut self) {
+        let Some(output) = self.workspaces.current().outputs().next().cloned() else {
+            return;
+        };
+        if self.workspaces.output_under(self.pointer_location).as_ref() == Some(&output) {
+            return;
+        }
+        if let Some(geo) = self.workspaces.output_geometry(&output) {
+            self.pointer_location =
+                Point::from((geo.loc.x + geo.size.w / 2, geo.loc.y + geo.size.h / 2)).to_f64();
+        }
+    }
+
     pub fn set_input_focus(&mut self, target: FocusTarget) {
         let keyboard = self.seat.get_keyboard().unwrap();
         let serial = SERIAL_COUNTER.next_serial();
         keyboard.set_focus(self, Some(target), serial);
     }
 
+    // Applies `CONFIG.focus_policy` after the pointer moved
+    fn focus_on_motion(&mut self) {
+        let under = self.surface_under().map(|(target, _)| target);
+        let entered = under != self.hovered;
+        self.hovered = under.clone();
+        let Some(target) = under else {
+            return;
+        };
+        match CONFIG.focus_policy {
+            FocusPolicy::FollowsMouse => self.focus_after_delay(target),
+            FocusPolicy::Sloppy if entered => self.focus_after_delay(target),
+            _ => {}
+        }
+    }
+
+    // Advances the pending chord with the pressed key. Returns the filter result if
+    // the key belongs to a chord, keys that break a chord are handled as usual.
+    fn chord_input(
+        &mut self,
+        modifiers: &ModifiersState,
+        handle: &KeysymHandle<'_>,
+    ) -> Option<FilterResult<Option<Action>>> {
+        // modifiers pressed for the next key of a chord don't break it
+        if (keysyms::KEY_Shift_L..=keysyms::KEY_Hyper_R).contains(&handle.modified_sym()) {
+            return None;
+        }
+        let typed = self.pending_chord.len();
+        let mut next = None;
+        for (chord, action) in CONFIG.chords.iter() {
+            if chord.len() > typed
+                && chord[..typed] == self.pending_chord[..]
+                && chord[typed].matches(modifiers, handle)
+            {
+                if chord.len() == typed + 1 {
+                    self.cancel_chord();
+                    return Some(FilterResult::Intercept(Some(action.clone())));
+                }
+                next = Some(chord[typed].clone());
+            }
+        }
+        let next = match next {
+            Some(next) => next,
+            None => {
+                self.cancel_chord();
+                return None;
+            }
+        };
+        self.cancel_chord_timer();
+        self.pending_chord.push(next);
+        let timer = Timer::from_duration(Duration::from_millis(CONFIG.chord_timeout));
+        self.chord_timer = self
+            .loop_handle
+            .insert_source(timer, |_, _, data| {
+                data.state.chord_timer = None;
+                data.state.pending_chord.clear();
+                TimeoutAction::Drop
+            })
+            .ok();
+        Some(FilterResult::Intercept(None))
+    }
+
+    fn cancel_chord_timer(&mut self) {
+        if let Some(token) = self.chord_timer.take() {
+            self.loop_handle.remove(token);
+        }
+    }
+
+    fn cancel_chord(&mut self) {
+        self.cancel_chord_timer();
+        self.pending_chord.clear();
+    }
+
+    // Repeats the action of a repeat binding until the key is released
+    fn start_repeat(&mut self, keycode: u32, action: Action) {
+        if let Some((_, token)) = self.repeat_timer.take() {
+            self.loop_handle.remove(token);
+        }
+        let keyboard = &CONFIG.keyboard;
+        let interval = Duration::from_millis(1000 / keyboard.repeat_rate.max(1) as u64);
+        let delay = Duration::from_millis(keyboard.repeat_delay.max(0) as u64);
+        let timer = Timer::from_duration(delay);
+        self.repeat_timer = self
+            .loop_handle
+            .insert_source(timer, move |_, _, data| {
+                data.state.handle_action(action.clone());
+                TimeoutAction::ToDuration(interval)
+            })
+            .ok()
+            .map(|token| (keycode, token));
+    }
+
+    // Focuses `target` once the pointer rested on it for `CONFIG.focus_delay`,
+    // only the last scheduled focus change counts
+    fn focus_after_delay(&mut self, target: FocusTarget) {
+        if let Some(token) = self.focus_timer.take() {
+            self.loop_handle.remove(token);
+        }
+        if self.focused_window().map(FocusTarget::Window).as_ref() == Some(&target) {
+            return;
+        }
+        if CONFIG.focus_delay == 0 {
+            self.set_input_focus(target);
+            return;
+        }
+        let timer = Timer::from_duration(Duration::from_millis(CONFIG.focus_delay));
+        self.focus_timer = self
+            .loop_handle
+            .insert_source(timer, move |_, _, data| {
+                let state = &mut data.state;
+                state.focus_timer = None;
+                if state.hovered.as_ref() == Some(&target) {
+                    state.set_input_focus(target.clone());
+                }
+                TimeoutAction::Drop
+            })
+            .ok();
+    }
+
+    // Focuses the window under the pointer, returns false if there is none
+    fn focus_under_pointer(&mut self) -> bool {
+        match self.surface_under() {
+            Some((target, _)) => {
+                self.set_input_focus(target);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Moves the focus after the current workspace changed or the focused window went away.
+    // It goes to the window under the pointer if the focus follows the mouse, otherwise
+    // (or with nothing under the pointer) to the window of the current workspace that
+    // was focused last.
     pub fn set_input_focus_auto(&mut self) {
-        let under = self.surface_under();
-        if let Some(d) = under {
-            self.set_input_focus(d.0);
+        if CONFIG.focus_policy == FocusPolicy::FollowsMouse && self.focus_under_pointer() {
+            return;
         }
+        let target = {
+            let workspace = self.workspaces.current();
+            self.focus_history
+                .iter()
+                .find(|h| workspace.contains_window(h))
+                .cloned()
+                .or_else(|| workspace.windows().next().map(|w| w.clone()))
+        };
+        let keyboard = self.seat.get_keyboard().unwrap();
+        let serial = SERIAL_COUNTER.next_serial();
+        keyboard.set_focus(self, target.map(FocusTarget::Window), serial);
     }
 
     pub fn handle_action(&mut self, action: Action) {
         match action {
-            Action::Quit => self.loop_signal.stop(),
+            Action::Quit => {
+                if CONFIG.persist_layouts {
+                    self.workspaces.save_layouts();
+                }
+                self.loop_signal.stop()
+            }
             Action::Close => {
                 if let Some(d) = self
                     .workspaces
@@ -198,11 +824,12 @@ impl<BackendData: Backend> CompState<BackendData> {
                     d.0.toplevel().send_close()
                 }
             }
-            Action::Workspace(id) => {
-                self.workspaces.activate(id);
+            Action::Workspace(name) => {
+                self.workspaces.activate(&name);
+                self.warp_to_current_output();
                 self.set_input_focus_auto();
             }
-            Action::MoveWindow(id) => {
+            Action::MoveWindow(name) => {
                 let window = self
                     .workspaces
                     .current()
@@ -210,12 +837,114 @@ impl<BackendData: Backend> CompState<BackendData> {
                     .map(|d| d.0.clone());
 
                 if let Some(window) = window {
-                    self.workspaces.move_window_to_workspace(&window, id);
+                    self.workspaces.move_window_to_workspace(&window, &name);
+                }
+            }
+            Action::MoveAndSwitch(name) => {
+                self.handle_action(Action::MoveWindow(name.clone()));
+                self.handle_action(Action::Workspace(name));
+            }
+            Action::WorkspaceNext | Action::WorkspacePrev => {
+                let forward = matches!(action, Action::WorkspaceNext);
+                if let Some(name) = self.workspaces.neighbour(forward) {
+                    self.handle_action(Action::Workspace(name));
+                }
+            }
+            Action::WorkspaceBackAndForth => {
+                if let Some(name) = self.workspaces.previous.clone() {
+                    self.handle_action(Action::Workspace(name));
+                }
+            }
+            Action::MoveWindowNext | Action::MoveWindowPrev => {
+                let forward = matches!(action, Action::MoveWindowNext);
+                if let Some(name) = self.workspaces.neighbour(forward) {
+                    self.handle_action(Action::MoveWindow(name));
+                }
+            }
+            Action::MoveWindowBackAndForth => {
+                if let Some(name) = self.workspaces.previous.clone() {
+                    self.handle_action(Action::MoveWindow(name));
+                }
+            }
+            Action::Mark(mark) => {
+                if let Some(window) = self.focused_window() {
+                    self.workspaces.mark(&window, &mark);
                 }
             }
-            Action::MoveAndSwitch(u8) => {
-                self.handle_action(Action::MoveWindow(u8));
-                self.handle_action(Action::Workspace(u8));
+            Action::FocusMark(mark) => {
+                if let Some(window) = self.workspaces.marked(&mark) {
+                    self.focus_window(window);
+                }
+            }
+            Action::SwapWithMark(mark) => {
+                if let (Some(focused), Some(marked)) =
+                    (self.focused_window(), self.workspaces.marked(&mark))
+                {
+                    self.workspaces.swap_windows(&focused, &marked);
+                    // the focus follows the focused window while it is still shown,
+                    // otherwise it goes to the window that took its place
+                    let focus = if self.workspaces.is_window_shown(&focused) {
+                        focused
+                    } else {
+                        marked
+                    };
+                    self.set_input_focus(FocusTarget::Window(focus));
+                }
+            }
+            Action::ToggleSticky => {
+                if let Some(window) = self.focused_window() {
+                    self.workspaces.toggle_sticky(&window);
+                }
+            }
+            Action::MoveToScratchpad => {
+                if let Some(window) = self.focused_window() {
+                    self.workspaces.move_to_scratchpad(&window);
+                    self.set_input_focus_auto();
+                }
+            }
+            Action::ToggleScratchpad(None) => {
+                match self.workspaces.toggle_scratchpad() {
+                    Some(window) => self.set_input_focus(FocusTarget::Window(window)),
+                    None => self.set_input_focus_auto(),
+                }
+            }
+            Action::ToggleScratchpad(Some(name)) => {
+                match self.workspaces.toggle_named_scratchpad(&name) {
+                    ScratchpadToggle::Spawn => {
+                        if let Some(scratchpad) = CONFIG.scratchpads.get(&name) {
+                            self.handle_action(Action::Spawn(scratchpad.command.clone()));
+                        }
+                    }
+                    ScratchpadToggle::Pending => {}
+                    ScratchpadToggle::Shown(window) => {
+                        self.set_input_focus(FocusTarget::Window(window))
+                    }
+                    ScratchpadToggle::Hidden => self.set_input_focus_auto(),
+                }
+            }
+            Action::EnterMode(mode) => {
+                if mode == DEFAULT_MODE {
+                    self.modes.clear();
+                } else if CONFIG.modes.contains_key(&mode) {
+                    self.modes.push(mode);
+                }
+                status::publish("mode", self.modes.last().map_or(DEFAULT_MODE, |m| m));
+            }
+            Action::ExitMode => {
+                self.modes.pop();
+                status::publish("mode", self.modes.last().map_or(DEFAULT_MODE, |m| m));
+            }
+            Action::MoveWithPointer => {
+                let under = self.window_under();
+                if let Some((window, _)) = under {
+                    if let Some(compwindow) = self.workspaces.comp_window(&window) {
+                        let compwindow = compwindow.borrow();
+                        if compwindow.floating {
+                            let offset = self.pointer_location - compwindow.rec.loc.to_f64();
+                            self.pointer_drag = Some((window, offset));
+                        }
+                    }
+                }
             }
             Action::Spawn(command) => {
                 if let Err(err) = std::process::Command::new("/bin/sh")
@@ -227,18 +956,208 @@ impl<BackendData: Backend> CompState<BackendData> {
                 }
             }
             Action::DecreaseTileRatio => {
-                self.workspaces
-                    .current_mut()
-                    .layout_tree
-                    .update_ratio(CONFIG.tile_ratio_update_interval, Some(false));
-                bsp_update_layout(self.workspaces.current_mut())
+                let workspace = self.workspaces.current_mut();
+                workspace
+                    .layout
+                    .resize(CONFIG.tile_ratio_update_interval, false);
+                update_layout(workspace)
             }
             Action::IncreaseTileRatio => {
-                self.workspaces
-                    .current_mut()
-                    .layout_tree
-                    .update_ratio(CONFIG.tile_ratio_update_interval, Some(true));
-                bsp_update_layout(self.workspaces.current_mut())
+                let workspace = self.workspaces.current_mut();
+                workspace
+                    .layout
+                    .resize(CONFIG.tile_ratio_update_interval, true);
+                update_layout(workspace)
+            }
+            Action::IncreaseMasterCount => {
+                let workspace = self.workspaces.current_mut();
+                workspace.layout.change_master_count(1);
+                update_layout(workspace)
+            }
+            Action::DecreaseMasterCount => {
+                let workspace = self.workspaces.current_mut();
+                workspace.layout.change_master_count(-1);
+                update_layout(workspace)
+            }
+            Action::FocusNext => self.cycle_focus(true),
+            Action::FocusPrev => self.cycle_focus(false),
+            Action::FocusLast => {
+                if let Some(window) = self.focus_history.get(1).cloned() {
+                    self.focus_window(window);
+                }
+            }
+            Action::NextKeyboardLayout => {
+                let index = (self.keyboard_layout + 1) % CONFIG.keyboard.layout_count();
+                self.set_keyboard_layout(index);
+            }
+            Action::SwitcherNext | Action::SwitcherPrev => {
+                let forward = matches!(action, Action::SwitcherNext);
+                match &mut self.switcher {
+                    Some(switcher) => switcher.cycle(forward),
+                    None => {
+                        // hidden scratchpad windows can't be switched to
+                        let candidates: Vec<Window> = self
+                            .workspaces
+                            .workspace_windows()
+                            .map(|w| w.clone())
+                            .collect();
+                        // most recently used first, windows never focused after them
+                        let mut windows: Vec<Window> = self
+                            .focus_history
+                            .iter()
+                            .filter(|w| candidates.contains(w))
+                            .cloned()
+                            .collect();
+                        for window in candidates {
+                            if !windows.contains(&window) {
+                                windows.push(window);
+                            }
+                        }
+                        self.switcher = Some(Switcher::new(windows, forward));
+                    }
+                }
+            }
+            Action::SetLayout(kind) => {
+                self.workspaces.current_mut().set_layout(kind);
+                self.set_input_focus_auto();
+            }
+            Action::CycleLayout => {
+                let workspace = self.workspaces.current_mut();
+                let kind = workspace.layout.kind().next();
+                workspace.set_layout(kind);
+                self.set_input_focus_auto();
+            }
+            Action::CycleColumnWidth => {
+                let workspace = self.workspaces.current_mut();
+                workspace.layout.cycle_width();
+                update_layout(workspace)
+            }
+            Action::MoveWindowLeft => {
+                self.update_focused_layout(|layout, window| layout.move_window(window, false))
+            }
+            Action::MoveWindowRight => {
+                self.update_focused_layout(|layout, window| layout.move_window(window, true))
+            }
+            Action::LayoutTabbed => self.update_focused_layout(|layout, window| {
+                layout.set_container(window, Some(ContainerKind::Tabbed))
+            }),
+            Action::LayoutStacked => self.update_focused_layout(|layout, window| {
+                layout.set_container(window, Some(ContainerKind::Stacked))
+            }),
+            Action::LayoutSplit => {
+                self.update_focused_layout(|layout, window| layout.set_container(window, None))
+            }
+            Action::AdjustGaps(kind, delta) => {
+                let workspace = self.workspaces.current_mut();
+                let Some(output) = workspace.outputs().next().cloned() else {
+                    return;
+                };
+                let (outer, inner) = configured_gaps(workspace, &output.name());
+                // never shrink below zero, so growing again takes effect right away
+                match kind {
+                    GapKind::Inner => workspace.gaps_delta.1 += (inner + delta).max(0) - inner,
+                    GapKind::Outer => workspace.gaps_delta.0 += (outer + delta).max(0) - outer,
+                }
+                update_layout(workspace)
+            }
+            Action::SaveLayout(name) => {
+                if let Some(tree) = self.workspaces.current().layout.save() {
+                    if let Err(err) = saved_layout::save(&CONFIG.layout_dir, &name, &tree) {
+                        eprintln!("{} Failed to save layout {}", err, name);
+                    }
+                }
+            }
+            Action::LoadLayout(name) => match saved_layout::load(&CONFIG.layout_dir, &name) {
+                Ok(tree) => self.workspaces.current_mut().load_layout(tree),
+                Err(err) => eprintln!("{} Failed to load layout {}", err, name),
+            },
+            Action::NextTab => self.cycle_tab(true),
+            Action::PrevTab => self.cycle_tab(false),
+            Action::MoveIntoContainer => {
+                self.update_focused_layout(|layout, window| layout.move_into_container(window))
+            }
+            Action::MoveOutOfContainer => {
+                self.update_focused_layout(|layout, window| layout.move_out_of_container(window))
+            }
+        }
+    }
+
+    pub fn set_keyboard_layout(&mut self, index: usize) {
+        if index == self.keyboard_layout {
+            return;
+        }
+        // the layouts are groups of one keymap, locking another group keeps the keymap
+        let keyboard = self.seat.get_keyboard().unwrap();
+        keyboard.with_xkb_state(self, |mut context| {
+            context.set_layout(keyboard::Layout(index as u32));
+        });
+        self.keyboard_layout_changed(index);
+    }
+
+    // Follows the active xkb group, switched by an action or by the keymap itself
+    fn keyboard_layout_changed(&mut self, index: usize) {
+        self.keyboard_layout = index;
+        status::publish("keyboard_layout", CONFIG.keyboard.layout_name(index));
+        if CONFIG.keyboard.per_window_layout {
+            if let Some(window) = self.focused_window() {
+                if let Some(compwindow) = self.workspaces.comp_window(&window) {
+                    compwindow.borrow_mut().keyboard_layout = index;
+                }
+            }
+        }
+    }
+
+    // Switches to the workspace of `window` and focuses it
+    fn focus_window(&mut self, window: Window) {
+        let Some(name) = self
+            .workspaces
+            .workspace_from_window(&window)
+            .map(|w| w.name.clone())
+        else {
+            return;
+        };
+        self.workspaces.activate(&name);
+        self.warp_to_current_output();
+        self.set_input_focus(FocusTarget::Window(window));
+    }
+
+    fn focused_window(&self) -> Option<Window> {
+        match self.seat.get_keyboard().unwrap().current_focus() {
+            Some(FocusTarget::Window(w)) => Some(w),
+            None => None,
+        }
+    }
+
+    fn cycle_focus(&mut self, forward: bool) {
+        let current = self.focused_window();
+        if let Some(window) = self
+            .workspaces
+            .current()
+            .next_window(current.as_ref(), forward)
+        {
+            self.set_input_focus(FocusTarget::Window(window));
+        }
+    }
+
+    fn cycle_tab(&mut self, forward: bool) {
+        let Some(window) = self.focused_window() else {
+            return;
+        };
+        let next = self
+            .workspaces
+            .workspace_from_window(&window)
+            .and_then(|workspace| workspace.layout.cycle_tab(&window, forward));
+        if let Some(next) = next {
+            self.set_input_focus(FocusTarget::Window(next));
+        }
+    }
+
+    // Runs `f` on the layout of the workspace holding the focused window and retiles it
+    fn update_focused_layout<F: FnOnce(&mut dyn Layout, &Window)>(&mut self, f: F) {
+        if let Some(window) = self.focused_window() {
+            if let Some(workspace) = self.workspaces.workspace_from_window(&window) {
+                f(workspace.layout.as_mut(), &window);
+                update_layout(workspace);
             }
         }
     }
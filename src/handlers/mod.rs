@@ -1,7 +1,8 @@
 use smithay::{
     backend::renderer::utils::on_commit_buffer_handler,
-    delegate_compositor, delegate_data_device, delegate_output,
-    delegate_primary_selection, delegate_seat, delegate_shm,
+    delegate_compositor, delegate_data_device, delegate_fractional_scale, delegate_output,
+    delegate_primary_selection, delegate_seat, delegate_shm, delegate_tablet_manager,
+    delegate_viewporter,
     input::{SeatHandler, SeatState},
     reexports::wayland_server::{
         protocol::wl_surface::WlSurface,
@@ -9,10 +10,13 @@ use smithay::{
     },
     wayland::{
         buffer::BufferHandler,
-        compositor::{get_parent, is_sync_subsurface, CompositorHandler, CompositorState},
+        compositor::{
+            get_parent, is_sync_subsurface, with_states, CompositorHandler, CompositorState,
+        },
         data_device::{
             set_data_device_focus, ClientDndGrabHandler, DataDeviceHandler, ServerDndGrabHandler,
         },
+        fractional_scale::{with_fractional_scale, FractionalScaleHandler},
         primary_selection::{set_primary_focus, PrimarySelectionHandler},
         seat::WaylandFocus,
         shm::{ShmHandler, ShmState},
@@ -20,11 +24,13 @@ use smithay::{
 };
 
 use crate::{
-    state::{Backend, CompState},
-    utils::focus::FocusTarget,
+    state::{Backend, CompState, CONFIG},
+    utils::{focus::FocusTarget, tiling::update_layout},
 };
 
 pub mod input;
+pub mod output_management;
+pub mod pointer_gestures;
 pub mod xdg_shell;
 
 impl<BackendData: Backend> CompositorHandler for CompState<BackendData> {
@@ -39,6 +45,14 @@ impl<BackendData: Backend> CompositorHandler for CompState<BackendData> {
             while let Some(parent) = get_parent(&root) {
                 root = parent;
             }
+            if let Some(index) = self
+                .unmapped_windows
+                .iter()
+                .position(|w| w.toplevel().wl_surface() == &root)
+            {
+                let window = self.unmapped_windows.remove(index);
+                self.map_window(window);
+            }
             if let Some(window) = self
                 .workspaces
                 .all_windows()
@@ -93,6 +107,20 @@ impl<BackendData: Backend> SeatHandler for CompState<BackendData> {
     if let Some(focus_target) = focused {
         match focus_target {
             FocusTarget::Window(w) => {
+                self.focus_history.retain(|h| h != w);
+                self.focus_history.insert(0, w.clone());
+                // the keyboard can't be changed while it is handing out the focus
+                if CONFIG.keyboard.per_window_layout {
+                    let layout = self
+                        .workspaces
+                        .comp_window(w)
+                        .map(|c| c.borrow().keyboard_layout);
+                    if let Some(layout) = layout.filter(|l| *l != self.keyboard_layout) {
+                        self.loop_handle.insert_idle(move |data| {
+                            data.state.set_keyboard_layout(layout);
+                        });
+                    }
+                }
                 for window in self.workspaces.all_windows() {
                     if window.eq(w) {
                         window.set_activated(true);
@@ -101,6 +129,17 @@ impl<BackendData: Backend> SeatHandler for CompState<BackendData> {
                     }
                     window.toplevel().send_configure();
                 }
+                if let Some(workspace) = self.workspaces.workspace_from_window(w) {
+                    // the layout might need to scroll to the newly focused window
+                    if workspace.layout.focus(w) {
+                        update_layout(workspace);
+                    }
+                    // focusing a window on another output focuses that output too
+                    let output = workspace.outputs().next().cloned();
+                    if let Some(output) = output {
+                        self.workspaces.focus_output(&output);
+                    }
+                }
             }
         };
     }
@@ -122,6 +161,34 @@ impl<BackendData: Backend> ClientDndGrabHandler for CompState<BackendData> {}
 impl<BackendData: Backend> ServerDndGrabHandler for CompState<BackendData> {}
 
 
+impl<BackendData: Backend> FractionalScaleHandler for CompState<BackendData> {
+    // the scale of the output the window is on, unmapped windows will open on the current workspace
+    fn new_fractional_scale(&mut self, surface: WlSurface) {
+        let mut root = surface.clone();
+        while let Some(parent) = get_parent(&root) {
+            root = parent;
+        }
+        let window = self
+            .workspaces
+            .all_windows()
+            .find(|w| w.toplevel().wl_surface() == &root)
+            .map(|w| w.clone());
+        let workspace = match window {
+            Some(window) => self.workspaces.workspace_from_window(&window).map(|w| &*w),
+            None => Some(self.workspaces.current()),
+        };
+        let Some(output) = workspace.and_then(|w| w.outputs().next()) else {
+            return;
+        };
+        let scale = output.current_scale().fractional_scale();
+        with_states(&surface, |states| {
+            with_fractional_scale(states, |fractional| {
+                fractional.set_preferred_scale(scale);
+            });
+        });
+    }
+}
+
 impl<BackendData: Backend,> PrimarySelectionHandler for CompState<BackendData> {
     fn primary_selection_state(
         &self,
@@ -136,3 +203,6 @@ delegate_seat!(@<BackendData: Backend + 'static> CompState<BackendData>);
 delegate_data_device!(@<BackendData: Backend + 'static> CompState<BackendData>);
 delegate_primary_selection!(@<BackendData: Backend + 'static> CompState<BackendData>);
 delegate_output!(@<BackendData: Backend + 'static> CompState<BackendData>);
+delegate_fractional_scale!(@<BackendData: Backend + 'static> CompState<BackendData>);
+delegate_viewporter!(@<BackendData: Backend + 'static> CompState<BackendData>);
+delegate_tablet_manager!(@<BackendData: Backend + 'static> CompState<BackendData>);
@@ -0,0 +1,482 @@
+use std::sync::{Arc, Mutex};
+
+use smithay::{
+    desktop::layer_map_for_output,
+    output::{Mode, Output},
+    reexports::{
+        wayland_protocols_wlr::output_management::v1::server::{
+            zwlr_output_configuration_head_v1::{self, ZwlrOutputConfigurationHeadV1},
+            zwlr_output_configuration_v1::{self, ZwlrOutputConfigurationV1},
+            zwlr_output_head_v1::{self, ZwlrOutputHeadV1},
+            zwlr_output_manager_v1::{self, ZwlrOutputManagerV1},
+            zwlr_output_mode_v1::{self, ZwlrOutputModeV1},
+        },
+        wayland_server::{
+            backend::{ClientId, GlobalId, ObjectId},
+            Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+        },
+    },
+    utils::Transform,
+};
+
+use crate::{
+    config::OutputConfig,
+    state::{Backend, CompState},
+    utils::workspace::Workspaces,
+};
+
+const VERSION: u32 = 3;
+
+// An output as advertised to one client
+struct Head {
+    output: Output,
+    head: ZwlrOutputHeadV1,
+    modes: Vec<ZwlrOutputModeV1>,
+}
+
+struct ManagerInstance {
+    manager: ZwlrOutputManagerV1,
+    heads: Vec<Head>,
+}
+
+/// State of the wlr-output-management global, which lets clients like
+/// wlr-randr and kanshi reconfigure the outputs.
+pub struct OutputManagementState {
+    _global: GlobalId,
+    managers: Vec<ManagerInstance>,
+    // every output of the backend, including the disabled ones
+    outputs: Vec<Output>,
+    // configurations created for an older serial are cancelled
+    serial: u32,
+}
+
+// The requested state of a head, written by `zwlr_output_configuration_head_v1`
+struct PendingHead {
+    output: Output,
+    config: OutputConfig,
+}
+
+struct PendingConfiguration {
+    serial: u32,
+    // a configuration can only be applied or tested once
+    used: bool,
+    // `None` disables the output
+    heads: Vec<(Output, Option<Arc<Mutex<PendingHead>>>)>,
+}
+
+impl OutputManagementState {
+    pub fn new<BackendData: Backend + 'static>(dh: &DisplayHandle) -> Self {
+        OutputManagementState {
+            _global: dh
+                .create_global::<CompState<BackendData>, ZwlrOutputManagerV1, _>(VERSION, ()),
+            managers: Vec::new(),
+            outputs: Vec::new(),
+            serial: 0,
+        }
+    }
+
+    // Advertises a new output of the backend to every client
+    pub fn add_output<BackendData: Backend + 'static>(
+        &mut self,
+        dh: &DisplayHandle,
+        output: &Output,
+        workspaces: &Workspaces,
+    ) {
+        self.outputs.push(output.clone());
+        for instance in &mut self.managers {
+            if let Some(head) = new_head::<BackendData>(dh, &instance.manager, output) {
+                instance.heads.push(head);
+            }
+        }
+        self.update::<BackendData>(dh, workspaces);
+    }
+
+    // Sends the current state of every output to all clients, needs to be called
+    // whenever an output changed
+    pub fn update<BackendData: Backend + 'static>(
+        &mut self,
+        dh: &DisplayHandle,
+        workspaces: &Workspaces,
+    ) {
+        self.serial = self.serial.wrapping_add(1);
+        for instance in &mut self.managers {
+            for head in &mut instance.heads {
+                send_modes::<BackendData>(dh, head);
+                send_state(head, workspaces);
+            }
+            instance.manager.done(self.serial);
+        }
+    }
+}
+
+fn new_head<BackendData: Backend + 'static>(
+    dh: &DisplayHandle,
+    manager: &ZwlrOutputManagerV1,
+    output: &Output,
+) -> Option<Head> {
+    let client = manager.client()?;
+    let head = client
+        .create_resource::<ZwlrOutputHeadV1, _, CompState<BackendData>>(
+            dh,
+            manager.version(),
+            output.clone(),
+        )
+        .ok()?;
+    manager.head(&head);
+
+    let properties = output.physical_properties();
+    head.name(output.name());
+    head.description(format!(
+        "{} {} ({})",
+        properties.make,
+        properties.model,
+        output.name()
+    ));
+    head.physical_size(properties.size.w, properties.size.h);
+    if head.version() >= 2 {
+        head.make(properties.make);
+        head.model(properties.model);
+    }
+
+    let mut head = Head {
+        output: output.clone(),
+        head,
+        modes: Vec::new(),
+    };
+    send_modes::<BackendData>(dh, &mut head);
+    Some(head)
+}
+
+// Advertises the modes of the output the client doesn't know yet
+fn send_modes<BackendData: Backend + 'static>(dh: &DisplayHandle, head: &mut Head) {
+    let Some(client) = head.head.client() else {
+        return;
+    };
+    for mode in head.output.modes() {
+        if head.modes.iter().any(|m| m.data::<Mode>() == Some(&mode)) {
+            continue;
+        }
+        let Ok(resource) = client.create_resource::<ZwlrOutputModeV1, _, CompState<BackendData>>(
+            dh,
+            head.head.version(),
+            mode,
+        ) else {
+            continue;
+        };
+        head.head.mode(&resource);
+        resource.size(mode.size.w, mode.size.h);
+        resource.refresh(mode.refresh);
+        if head.output.preferred_mode() == Some(mode) {
+            resource.preferred();
+        }
+        head.modes.push(resource);
+    }
+}
+
+// Outputs without a workspace are disabled
+fn send_state(head: &Head, workspaces: &Workspaces) {
+    let output = &head.output;
+    let enabled = workspaces.outputs().any(|o| o == output);
+    head.head.enabled(enabled as i32);
+    if !enabled {
+        return;
+    }
+    if let Some(mode) = head
+        .modes
+        .iter()
+        .find(|m| m.data::<Mode>().copied() == output.current_mode())
+    {
+        head.head.current_mode(mode);
+    }
+    let position = output.current_location();
+    head.head.position(position.x, position.y);
+    head.head.transform(output.current_transform().into());
+    head.head.scale(output.current_scale().fractional_scale());
+}
+
+impl<BackendData: Backend> GlobalDispatch<ZwlrOutputManagerV1, ()> for CompState<BackendData> {
+    fn bind(
+        state: &mut Self,
+        dh: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZwlrOutputManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let manager = data_init.init(resource, ());
+        let management = &mut state.output_management_state;
+        let heads: Vec<Head> = management
+            .outputs
+            .iter()
+            .filter_map(|output| new_head::<BackendData>(dh, &manager, output))
+            .collect();
+        for head in &heads {
+            send_state(head, &state.workspaces);
+        }
+        manager.done(management.serial);
+        management.managers.push(ManagerInstance { manager, heads });
+    }
+}
+
+impl<BackendData: Backend> Dispatch<ZwlrOutputManagerV1, ()> for CompState<BackendData> {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        manager: &ZwlrOutputManagerV1,
+        request: zwlr_output_manager_v1::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            zwlr_output_manager_v1::Request::CreateConfiguration { id, serial } => {
+                data_init.init(
+                    id,
+                    Mutex::new(PendingConfiguration {
+                        serial,
+                        used: false,
+                        heads: Vec::new(),
+                    }),
+                );
+            }
+            zwlr_output_manager_v1::Request::Stop => {
+                state
+                    .output_management_state
+                    .managers
+                    .retain(|m| &m.manager != manager);
+                manager.finished();
+            }
+            _ => {}
+        }
+    }
+
+    fn destroyed(state: &mut Self, _client: ClientId, resource: ObjectId, _data: &()) {
+        state
+            .output_management_state
+            .managers
+            .retain(|m| m.manager.id() != resource);
+    }
+}
+
+impl<BackendData: Backend> Dispatch<ZwlrOutputHeadV1, Output> for CompState<BackendData> {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _head: &ZwlrOutputHeadV1,
+        _request: zwlr_output_head_v1::Request,
+        _data: &Output,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        // the only request is release, the head is dropped with the manager
+    }
+}
+
+impl<BackendData: Backend> Dispatch<ZwlrOutputModeV1, Mode> for CompState<BackendData> {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _mode: &ZwlrOutputModeV1,
+        _request: zwlr_output_mode_v1::Request,
+        _data: &Mode,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+    }
+}
+
+impl<BackendData: Backend> Dispatch<ZwlrOutputConfigurationV1, Mutex<PendingConfiguration>>
+    for CompState<BackendData>
+{
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        configuration: &ZwlrOutputConfigurationV1,
+        request: zwlr_output_configuration_v1::Request,
+        data: &Mutex<PendingConfiguration>,
+        dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            zwlr_output_configuration_v1::Request::EnableHead { id, head } => {
+                let Some(output) = head.data::<Output>().cloned() else {
+                    return;
+                };
+                let pending = Arc::new(Mutex::new(PendingHead {
+                    output: output.clone(),
+                    config: OutputConfig::default(),
+                }));
+                if add_head(configuration, data, output, Some(pending.clone())) {
+                    data_init.init(id, pending);
+                }
+            }
+            zwlr_output_configuration_v1::Request::DisableHead { head } => {
+                if let Some(output) = head.data::<Output>().cloned() {
+                    add_head(configuration, data, output, None);
+                }
+            }
+            zwlr_output_configuration_v1::Request::Apply => {
+                state.finish_configuration::<false>(dh, configuration, data)
+            }
+            zwlr_output_configuration_v1::Request::Test => {
+                state.finish_configuration::<true>(dh, configuration, data)
+            }
+            _ => {}
+        }
+    }
+}
+
+// Returns false if the head was already part of the configuration
+fn add_head(
+    configuration: &ZwlrOutputConfigurationV1,
+    data: &Mutex<PendingConfiguration>,
+    output: Output,
+    pending: Option<Arc<Mutex<PendingHead>>>,
+) -> bool {
+    let mut data = data.lock().unwrap();
+    if data.heads.iter().any(|(o, _)| o == &output) {
+        configuration.post_error(
+            zwlr_output_configuration_v1::Error::AlreadyConfiguredHead,
+            "head has been configured twice",
+        );
+        return false;
+    }
+    data.heads.push((output, pending));
+    true
+}
+
+impl<BackendData: Backend> Dispatch<ZwlrOutputConfigurationHeadV1, Arc<Mutex<PendingHead>>>
+    for CompState<BackendData>
+{
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        resource: &ZwlrOutputConfigurationHeadV1,
+        request: zwlr_output_configuration_head_v1::Request,
+        data: &Arc<Mutex<PendingHead>>,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        use zwlr_output_configuration_head_v1::{Error, Request};
+
+        let mut pending = data.lock().unwrap();
+        let already_set = match &request {
+            Request::SetMode { .. } | Request::SetCustomMode { .. } => {
+                pending.config.mode.is_some()
+            }
+            Request::SetPosition { .. } => pending.config.position.is_some(),
+            Request::SetTransform { .. } => pending.config.transform.is_some(),
+            Request::SetScale { .. } => pending.config.scale.is_some(),
+            _ => false,
+        };
+        if already_set {
+            resource.post_error(Error::AlreadySet, "property has already been set");
+            return;
+        }
+
+        match request {
+            Request::SetMode { mode } => match mode.data::<Mode>() {
+                Some(mode) => {
+                    pending.config.mode =
+                        Some(((mode.size.w, mode.size.h), Some(mode.refresh as u32)))
+                }
+                None => resource.post_error(Error::InvalidMode, "unknown mode"),
+            },
+            // only the advertised modes can be used, a refresh rate of 0 accepts any of them
+            Request::SetCustomMode {
+                width,
+                height,
+                refresh,
+            } => {
+                let refresh = (refresh > 0).then_some(refresh as u32);
+                let available = pending.output.modes().into_iter().any(|mode| {
+                    mode.size == (width, height).into()
+                        && refresh.is_none_or(|r| mode.refresh == r as i32)
+                });
+                if available {
+                    pending.config.mode = Some(((width, height), refresh));
+                } else {
+                    resource.post_error(Error::InvalidCustomMode, "mode is not supported");
+                }
+            }
+            Request::SetPosition { x, y } => pending.config.position = Some((x, y)),
+            Request::SetTransform { transform } => match transform.into_result() {
+                Ok(transform) => pending.config.transform = Some(Transform::from(transform)),
+                Err(_) => resource.post_error(Error::InvalidTransform, "unknown transform"),
+            },
+            Request::SetScale { scale } if scale > 0.0 => pending.config.scale = Some(scale),
+            Request::SetScale { .. } => resource.post_error(Error::InvalidScale, "invalid scale"),
+            _ => {}
+        }
+    }
+}
+
+impl<BackendData: Backend> CompState<BackendData> {
+    // Validates the configuration and applies it unless `TEST_ONLY` is set
+    fn finish_configuration<const TEST_ONLY: bool>(
+        &mut self,
+        dh: &DisplayHandle,
+        configuration: &ZwlrOutputConfigurationV1,
+        data: &Mutex<PendingConfiguration>,
+    ) {
+        let mut data = data.lock().unwrap();
+        if data.used {
+            configuration.post_error(
+                zwlr_output_configuration_v1::Error::AlreadyUsed,
+                "configuration has already been used",
+            );
+            return;
+        }
+        data.used = true;
+
+        let management = &self.output_management_state;
+        if let Some(missing) = management
+            .outputs
+            .iter()
+            .find(|o| !data.heads.iter().any(|(h, _)| &h == o))
+        {
+            configuration.post_error(
+                zwlr_output_configuration_v1::Error::UnconfiguredHead,
+                format!("head {} has not been configured", missing.name()),
+            );
+            return;
+        }
+        if data.serial != management.serial {
+            configuration.cancelled();
+            return;
+        }
+        // windows need somewhere to go
+        if data.heads.iter().all(|(_, pending)| pending.is_none()) {
+            configuration.failed();
+            return;
+        }
+        if TEST_ONLY {
+            configuration.succeeded();
+            return;
+        }
+
+        for (output, pending) in &data.heads {
+            let enabled = self.workspaces.outputs().any(|o| o == output);
+            match pending {
+                Some(pending) => {
+                    let location = output.current_location();
+                    pending.lock().unwrap().config.apply(output);
+                    if enabled {
+                        let offset = output.current_location() - location;
+                        self.workspaces.translate_floating(output, offset);
+                    }
+                    if !enabled {
+                        self.workspaces.add_output(output.clone());
+                    }
+                    layer_map_for_output(output).arrange();
+                    self.workspaces.retile(output);
+                }
+                None if enabled => self.workspaces.remove_output(output),
+                None => {}
+            }
+        }
+        configuration.succeeded();
+        self.output_management_state
+            .update::<BackendData>(dh, &self.workspaces);
+    }
+}
@@ -0,0 +1,186 @@
+use smithay::{
+    reexports::{
+        wayland_protocols::wp::pointer_gestures::zv1::server::{
+            zwp_pointer_gesture_pinch_v1::{self, ZwpPointerGesturePinchV1},
+            zwp_pointer_gesture_swipe_v1::{self, ZwpPointerGestureSwipeV1},
+            zwp_pointer_gestures_v1::{self, ZwpPointerGesturesV1},
+        },
+        wayland_server::{
+            backend::{ClientId, GlobalId, ObjectId},
+            protocol::wl_surface::WlSurface,
+            Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+        },
+    },
+    utils::{Logical, Point, Serial},
+};
+
+use crate::state::{Backend, CompState};
+
+const VERSION: u32 = 2;
+
+/// State of the pointer-gestures global, which forwards the touchpad gestures
+/// the compositor doesn't handle itself to the client under the pointer.
+pub struct PointerGesturesState {
+    _global: GlobalId,
+    swipes: Vec<ZwpPointerGestureSwipeV1>,
+    pinches: Vec<ZwpPointerGesturePinchV1>,
+    // surface the running gesture began on and whether it is a pinch
+    active: Option<(WlSurface, bool)>,
+}
+
+impl PointerGesturesState {
+    pub fn new<BackendData: Backend + 'static>(dh: &DisplayHandle) -> Self {
+        PointerGesturesState {
+            _global: dh
+                .create_global::<CompState<BackendData>, ZwpPointerGesturesV1, _>(VERSION, ()),
+            swipes: Vec::new(),
+            pinches: Vec::new(),
+            active: None,
+        }
+    }
+
+    pub fn begin(
+        &mut self,
+        pinch: bool,
+        surface: WlSurface,
+        serial: Serial,
+        time: u32,
+        fingers: u32,
+    ) {
+        self.active = Some((surface.clone(), pinch));
+        if pinch {
+            for gesture in self.focused(&self.pinches) {
+                gesture.begin(serial.into(), time, &surface, fingers);
+            }
+        } else {
+            for gesture in self.focused(&self.swipes) {
+                gesture.begin(serial.into(), time, &surface, fingers);
+            }
+        }
+    }
+
+    pub fn update(&self, time: u32, delta: Point<f64, Logical>, scale: f64, rotation: f64) {
+        match self.active {
+            Some((_, true)) => {
+                for gesture in self.focused(&self.pinches) {
+                    gesture.update(time, delta.x, delta.y, scale, rotation);
+                }
+            }
+            Some((_, false)) => {
+                for gesture in self.focused(&self.swipes) {
+                    gesture.update(time, delta.x, delta.y);
+                }
+            }
+            None => {}
+        }
+    }
+
+    pub fn end(&mut self, serial: Serial, time: u32, cancelled: bool) {
+        match self.active {
+            Some((_, true)) => {
+                for gesture in self.focused(&self.pinches) {
+                    gesture.end(serial.into(), time, cancelled as i32);
+                }
+            }
+            Some((_, false)) => {
+                for gesture in self.focused(&self.swipes) {
+                    gesture.end(serial.into(), time, cancelled as i32);
+                }
+            }
+            None => {}
+        }
+        self.active = None;
+    }
+
+    fn surface(&self) -> Option<&WlSurface> {
+        self.active.as_ref().map(|(surface, _)| surface)
+    }
+
+    // The gesture objects of the client the running gesture began on
+    fn focused<'a, G: Resource>(&'a self, gestures: &'a [G]) -> impl Iterator<Item = &'a G> {
+        gestures.iter().filter(move |gesture| {
+            self.surface()
+                .map_or(false, |surface| gesture.id().same_client_as(&surface.id()))
+        })
+    }
+}
+
+impl<BackendData: Backend> GlobalDispatch<ZwpPointerGesturesV1, ()> for CompState<BackendData> {
+    fn bind(
+        _state: &mut Self,
+        _dh: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZwpPointerGesturesV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl<BackendData: Backend> Dispatch<ZwpPointerGesturesV1, ()> for CompState<BackendData> {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        _gestures: &ZwpPointerGesturesV1,
+        request: zwp_pointer_gestures_v1::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        // there is a single seat, so the pointer the gesture is for doesn't matter
+        match request {
+            zwp_pointer_gestures_v1::Request::GetSwipeGesture { id, .. } => {
+                let swipe = data_init.init(id, ());
+                state.pointer_gestures_state.swipes.push(swipe);
+            }
+            zwp_pointer_gestures_v1::Request::GetPinchGesture { id, .. } => {
+                let pinch = data_init.init(id, ());
+                state.pointer_gestures_state.pinches.push(pinch);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<BackendData: Backend> Dispatch<ZwpPointerGestureSwipeV1, ()> for CompState<BackendData> {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _swipe: &ZwpPointerGestureSwipeV1,
+        _request: zwp_pointer_gesture_swipe_v1::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        // the only request is destroy
+    }
+
+    fn destroyed(state: &mut Self, _client: ClientId, resource: ObjectId, _data: &()) {
+        state
+            .pointer_gestures_state
+            .swipes
+            .retain(|swipe| swipe.id() != resource);
+    }
+}
+
+impl<BackendData: Backend> Dispatch<ZwpPointerGesturePinchV1, ()> for CompState<BackendData> {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _pinch: &ZwpPointerGesturePinchV1,
+        _request: zwp_pointer_gesture_pinch_v1::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        // the only request is destroy
+    }
+
+    fn destroyed(state: &mut Self, _client: ClientId, resource: ObjectId, _data: &()) {
+        state
+            .pointer_gestures_state
+            .pinches
+            .retain(|pinch| pinch.id() != resource);
+    }
+}
@@ -10,7 +10,7 @@ use smithay::{
         },
         wayland_server::protocol::{wl_seat::WlSeat, wl_surface::WlSurface},
     },
-    utils::Serial,
+    utils::{Logical, Point, Rectangle, Serial, Size},
     wayland::{
         compositor::with_states,
         shell::{
@@ -26,10 +26,11 @@ use smithay::{
 use std::{cell::RefCell, rc::Rc, sync::Mutex};
 
 use crate::{
-    state::{Backend, CompState},
+    config::Floating,
+    state::{Backend, CompState, CONFIG},
     utils::{
         focus::FocusTarget,
-        workspace::{CompWindow, Workspaces},
+        workspace::{window_identity, CompWindow, Workspaces},
     },
 };
 
@@ -39,28 +40,45 @@ impl<BackendData: Backend> XdgShellHandler for CompState<BackendData> {
     }
 
     fn new_toplevel(&mut self, surface: ToplevelSurface) {
-        let window = Window::new(surface);
-        self.workspaces
-            .current_mut()
-            .add_window(Rc::new(RefCell::new(CompWindow {
-                window: window.clone(),
-                rec: window.geometry(),
-            })));
-        self.set_input_focus(FocusTarget::Window(window));
+        // app_id and title are not known yet, the window is placed on its initial commit
+        self.unmapped_windows.push(Window::new(surface));
     }
     fn toplevel_destroyed(&mut self, surface: ToplevelSurface) {
-        let window = self
+        if let Some(index) = self
+            .unmapped_windows
+            .iter()
+            .position(|w| w.toplevel() == &surface)
+        {
+            self.unmapped_windows.remove(index);
+            return;
+        }
+        let Some(window) = self
             .workspaces
             .all_windows()
             .find(|w| w.toplevel() == &surface)
-            .unwrap()
-            .clone();
-
-        self.workspaces
-            .workspace_from_window(&window)
-            .unwrap()
-            .remove_window(&window);
-        self.set_input_focus_auto();
+            .map(|w| w.clone())
+        else {
+            return;
+        };
+
+        if let Some(workspace) = self.workspaces.workspace_from_window(&window) {
+            workspace.remove_window(&window);
+        }
+        self.workspaces.forget_scratchpad(&window);
+        self.workspaces.cleanup();
+        self.workspaces.publish_marks();
+
+        // hand the focus back to the window focused before
+        self.focus_history.retain(|h| h != &window);
+        let previous = self
+            .focus_history
+            .iter()
+            .find(|h| self.workspaces.is_window_shown(h))
+            .cloned();
+        match previous {
+            Some(previous) => self.set_input_focus(FocusTarget::Window(previous)),
+            None => self.set_input_focus_auto(),
+        }
     }
     fn new_popup(&mut self, surface: PopupSurface, positioner: PositionerState) {
         surface.with_pending_state(|state| {
@@ -78,6 +96,95 @@ impl<BackendData: Backend> XdgShellHandler for CompState<BackendData> {
 
 delegate_xdg_shell!(@<BackendData: Backend + 'static> CompState<BackendData>);
 
+impl<BackendData: Backend> CompState<BackendData> {
+    // Places a new toplevel according to the window rules
+    pub fn map_window(&mut self, window: Window) {
+        let (app_id, title) = window_identity(&window);
+
+        // the window of a named scratchpad that was just spawned
+        if let Some(name) = self.workspaces.waiting_scratchpad(app_id.as_deref()) {
+            if let Some(shown) = self
+                .workspaces
+                .capture_scratchpad(&name, CompWindow::new(window))
+            {
+                self.set_input_focus(FocusTarget::Window(shown));
+            }
+            return;
+        }
+
+        let mut workspace = None;
+        let mut floating: Option<Floating> = None;
+        let mut fullscreen = false;
+        let mut skip_focus = false;
+        let mut no_border = false;
+        let mut opacity = None;
+        for rule in CONFIG
+            .window_rules
+            .iter()
+            .filter(|r| r.matches(app_id.as_deref(), title.as_deref()))
+        {
+            workspace = rule.workspace.clone().or(workspace);
+            floating = rule.floating.clone().or(floating);
+            fullscreen |= rule.fullscreen;
+            skip_focus |= rule.skip_focus;
+            no_border |= rule.no_border;
+            opacity = rule.opacity.or(opacity);
+        }
+
+        // a loaded layout waiting for the window wins over the rules
+        let swallowed = self.workspaces.waiting_for(&window);
+        let name = swallowed
+            .clone()
+            .or(workspace)
+            .unwrap_or_else(|| self.workspaces.current.clone());
+        let ws = self.workspaces.get_or_create(&name);
+        let output_geo = ws
+            .outputs()
+            .next()
+            .and_then(|o| ws.output_geometry(o))
+            .unwrap_or_default();
+
+        let mut compwindow = CompWindow::new(window.clone());
+        compwindow.no_border = no_border;
+        compwindow.opacity = opacity.unwrap_or(1.0).clamp(0.0, 1.0);
+        let toplevel = window.toplevel();
+        if fullscreen {
+            compwindow.fullscreen = true;
+            toplevel.with_pending_state(|state| {
+                state.states.set(ToplevelState::Fullscreen);
+            });
+        } else if let (Some(floating), None) = (floating, &swallowed) {
+            let size: Size<i32, Logical> = floating
+                .size
+                .map(Size::from)
+                .unwrap_or_else(|| Size::from((output_geo.size.w / 2, output_geo.size.h / 2)));
+            let loc = floating
+                .position
+                .map(|position| output_geo.loc + Point::from(position))
+                .unwrap_or_else(|| {
+                    Point::from((
+                        output_geo.loc.x + (output_geo.size.w - size.w) / 2,
+                        output_geo.loc.y + (output_geo.size.h - size.h) / 2,
+                    ))
+                });
+            compwindow.floating = true;
+            compwindow.rec = Rectangle { loc, size };
+        } else {
+            toplevel.with_pending_state(|state| {
+                state.states.set(ToplevelState::TiledLeft);
+                state.states.set(ToplevelState::TiledRight);
+                state.states.set(ToplevelState::TiledTop);
+                state.states.set(ToplevelState::TiledBottom);
+            });
+        }
+        ws.add_window(Rc::new(RefCell::new(compwindow)));
+
+        if CONFIG.focus_new_windows && !skip_focus && name == self.workspaces.current {
+            self.set_input_focus(FocusTarget::Window(window));
+        }
+    }
+}
+
 // Should be called on `WlSurface::commit`
 pub fn handle_commit(workspaces: &Workspaces, surface: &WlSurface, popup_manager: &PopupManager) {
     if let Some(window) = workspaces
@@ -105,7 +212,7 @@ pub fn handle_commit(workspaces: &Workspaces, surface: &WlSurface, popup_manager
         }
     }
 
-    if let Some(output) = workspaces.current().outputs().find(|o| {
+    if let Some(output) = workspaces.outputs().find(|o| {
         let map = layer_map_for_output(o);
         map.layer_for_surface(surface, WindowSurfaceType::TOPLEVEL)
             .is_some()
@@ -2,12 +2,17 @@ use std::{ffi::OsString, os::fd::AsRawFd, sync::Arc, time::Instant};
 
 use once_cell::sync::Lazy;
 use smithay::{
+    backend::input::TouchSlot,
     desktop::{
         {PopupManager, Window},
     },
-    input::{keyboard::XkbConfig, Seat, SeatState},
+    input::{Seat, SeatState},
+    output::Output,
     reexports::{
-        calloop::{generic::Generic, Interest, LoopHandle, LoopSignal, Mode, PostAction},
+        calloop::{
+            generic::Generic, Interest, LoopHandle, LoopSignal, Mode, PostAction,
+            RegistrationToken,
+        },
         wayland_server::{
             backend::{ClientData, ClientId, DisconnectReason},
             Display, DisplayHandle,
@@ -16,15 +21,25 @@ use smithay::{
     utils::{Logical, Point},
     wayland::{
         compositor::CompositorState,
+        fractional_scale::FractionalScaleManagerState,
         output::OutputManagerState,
         shell::xdg::{decoration::XdgDecorationState, XdgShellState},
         shm::ShmState,
-        socket::ListeningSocketSource, {data_device::DataDeviceState, primary_selection::PrimarySelectionState},
+        socket::ListeningSocketSource,
+        tablet_manager::{TabletManagerState, TabletSeatTrait},
+        viewporter::ViewporterState,
+        {data_device::DataDeviceState, primary_selection::PrimarySelectionState},
     },
 };
 
-use crate::config::{generate_config, Config};
-use crate::utils::{focus::FocusTarget, workspace::Workspaces};
+use crate::config::{generate_config, Action, Config, KeyPattern, DEFAULT_MODE};
+use crate::handlers::{
+    output_management::OutputManagementState, pointer_gestures::PointerGesturesState,
+};
+use crate::utils::{
+    focus::FocusTarget, gesture::Gesture, status, switcher::Switcher, tablet::TabletCursor,
+    workspace::Workspaces,
+};
 
 pub struct CalloopData<BackendData: Backend + 'static> {
     pub state: CompState<BackendData>,
@@ -50,6 +65,11 @@ pub struct CompState<BackendData: Backend + 'static> {
     pub xdg_decoration_state: XdgDecorationState,
     pub shm_state: ShmState,
     pub output_manager_state: OutputManagerState,
+    pub output_management_state: OutputManagementState,
+    pub pointer_gestures_state: PointerGesturesState,
+    pub fractional_scale_manager_state: FractionalScaleManagerState,
+    pub viewporter_state: ViewporterState,
+    pub tablet_manager_state: TabletManagerState,
     pub data_device_state: DataDeviceState,
     pub primary_selection_state: PrimarySelectionState,
     pub seat_state: SeatState<CompState<BackendData>>,
@@ -60,7 +80,36 @@ pub struct CompState<BackendData: Backend + 'static> {
     pub socket_name: OsString,
 
     pub workspaces: Workspaces,
+    // toplevels waiting for their initial commit, see `CompState::map_window`
+    pub unmapped_windows: Vec<Window>,
     pub pointer_location: Point<f64, Logical>,
+    // the window the pointer was last over, see `FocusPolicy::Sloppy`
+    pub hovered: Option<FocusTarget>,
+    // pending focus change of `CONFIG.focus_delay`
+    pub focus_timer: Option<RegistrationToken>,
+    // windows focused through `seat`, the most recently focused first
+    pub focus_history: Vec<Window>,
+    pub switcher: Option<Switcher>,
+    // index into the layouts of `CONFIG.keyboard`
+    pub keyboard_layout: usize,
+    // entered binding modes, the active one last, empty in the default mode
+    pub modes: Vec<String>,
+    // keys of the chord typed so far, cleared by `chord_timer`
+    pub pending_chord: Vec<KeyPattern>,
+    pub chord_timer: Option<RegistrationToken>,
+    // release binding armed by the press of the key with this code
+    pub release_binding: Option<(u32, Action)>,
+    // repeat binding held down, by key code
+    pub repeat_timer: Option<(u32, RegistrationToken)>,
+    // button pressed for a mouse binding, its release is not sent to the client
+    pub bound_button: Option<u32>,
+    // window moved by `Action::MoveWithPointer` and the pointer offset from its origin
+    pub pointer_drag: Option<(Window, Point<f64, Logical>)>,
+    // touchpad gesture in progress
+    pub gesture: Option<Gesture>,
+    // touch point driving the pointer, as it went down outside of any window
+    pub touch_emulated: Option<TouchSlot>,
+    pub tablet_cursor: TabletCursor,
 }
 
 impl<BackendData: Backend> CompState<BackendData> {
@@ -79,17 +128,38 @@ impl<BackendData: Backend> CompState<BackendData> {
         let xdg_decoration_state = XdgDecorationState::new::<Self>(&dh);
         let shm_state = ShmState::new::<Self>(&dh, vec![]);
         let output_manager_state = OutputManagerState::new_with_xdg_output::<Self>(&dh);
+        let output_management_state = OutputManagementState::new::<BackendData>(&dh);
+        let pointer_gestures_state = PointerGesturesState::new::<BackendData>(&dh);
+        let fractional_scale_manager_state = FractionalScaleManagerState::new::<Self>(&dh);
+        let viewporter_state = ViewporterState::new::<Self>(&dh);
+        let tablet_manager_state = TabletManagerState::new::<Self>(&dh);
         let mut seat_state = SeatState::new();
         let data_device_state = DataDeviceState::new::<Self>(&dh);
         let primary_selection_state = PrimarySelectionState::new::<Self>(&dh);
         let seat_name = backend_data.seat_name();
         let mut seat = seat_state.new_wl_seat(&dh, seat_name.clone());
         
-        seat.add_keyboard(XkbConfig::default(), 200, 25)
+        let keyboard_config = &CONFIG.keyboard;
+        seat.add_keyboard(
+            keyboard_config.xkb_config(),
+            keyboard_config.repeat_delay,
+            keyboard_config.repeat_rate,
+        )
         .expect("Failed to load xkb configuration files");
+        status::publish("keyboard_layout", keyboard_config.layout_name(0));
+        status::publish("mode", DEFAULT_MODE);
         seat.add_pointer();
+        seat.add_touch();
+        let tablet_cursor = TabletCursor::default();
+        let tablet_image = tablet_cursor.image.clone();
+        seat.tablet_seat().on_cursor_surface(move |_tool, image| {
+            *tablet_image.lock().unwrap() = image;
+        });
 
-        let workspaces = Workspaces::new(CONFIG.workspaces);
+        let mut workspaces = Workspaces::new(CONFIG.workspaces);
+        if CONFIG.persist_layouts {
+            workspaces.restore_layouts();
+        }
 
         let socket_name = Self::init_wayland_listener(&mut loop_handle, display);
 
@@ -106,13 +176,34 @@ impl<BackendData: Backend> CompState<BackendData> {
             loop_signal,
             shm_state,
             output_manager_state,
+            output_management_state,
+            pointer_gestures_state,
+            fractional_scale_manager_state,
+            viewporter_state,
+            tablet_manager_state,
             popup_manager: PopupManager::default(),
             seat_state,
             data_device_state,
             primary_selection_state,
             seat,
             workspaces,
+            unmapped_windows: Vec::new(),
             pointer_location: Point::from((0.0, 0.0)),
+            hovered: None,
+            focus_timer: None,
+            focus_history: Vec::new(),
+            switcher: None,
+            keyboard_layout: 0,
+            modes: Vec::new(),
+            pending_chord: Vec::new(),
+            chord_timer: None,
+            release_binding: None,
+            repeat_timer: None,
+            bound_button: None,
+            pointer_drag: None,
+            gesture: None,
+            touch_emulated: None,
+            tablet_cursor,
         }
     }
     fn init_wayland_listener(
@@ -157,18 +248,36 @@ impl<BackendData: Backend> CompState<BackendData> {
         socket_name
     }
 
+    // Connects an output of the backend, with its settings from `CONFIG.outputs` applied
+    pub fn add_output(&mut self, output: Output)
+    where
+        BackendData: 'static,
+    {
+        if let Some(output_config) = CONFIG.output_config(&output) {
+            output_config.apply(&output);
+        }
+        self.workspaces.add_output(output.clone());
+        self.output_management_state
+            .add_output::<BackendData>(&self.dh, &output, &self.workspaces);
+    }
+
     //return the window under the cursor, if any
     pub fn window_under(&mut self) -> Option<(Window, Point<i32, Logical>)> {
         let pos = self.pointer_location;
         self.workspaces
-            .current()
+            .under(pos)
             .window_under(pos)
             .map(|(w, p)| (w.clone(), p))
     }
     pub fn surface_under(&self) -> Option<(FocusTarget, Point<i32, Logical>)> {
-        let pos = self.pointer_location;
+        self.surface_at(self.pointer_location)
+    }
+    pub fn surface_at(
+        &self,
+        pos: Point<f64, Logical>,
+    ) -> Option<(FocusTarget, Point<i32, Logical>)> {
         let mut under = None;
-        if let Some((window, location)) = self.workspaces.current().window_under(pos) {
+        if let Some((window, location)) = self.workspaces.under(pos).window_under(pos) {
             under = Some((window.clone().into(), location));
         }
         under
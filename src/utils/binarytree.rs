@@ -1,12 +1,25 @@
-use smithay::desktop::Window;
+use smithay::{
+    backend::renderer::element::solid::SolidColorBuffer,
+    desktop::Window,
+    utils::{Logical, Point, Rectangle, Size},
+};
+use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 use std::{cell::RefCell, rc::Rc};
-use super::workspace::CompWindow;
+use crate::{config::LayoutKind, state::CONFIG};
+use super::{
+    layout::{swap_slots, Layout},
+    saved_layout::{SavedTree, Swallow},
+    tiling::{bsp_arrange, shrink, split_rect},
+    workspace::CompWindow,
+};
 
 #[derive(Clone)]
 pub enum BinaryTree {
     Empty,
     Window(Rc<RefCell<CompWindow>>),
+    // empty tile of a loaded layout, waiting for a window matching the criteria
+    Placeholder(Swallow),
     Split {
         split: HorizontalOrVertical,
         ratio: f32,
@@ -14,9 +27,29 @@ pub enum BinaryTree {
         left: Box<BinaryTree>,
         right: Box<BinaryTree>,
     },
+    // i3-style container: several windows share one tile and only the `active` one is shown,
+    // `headers` are the tab headers drawn above it
+    Container {
+        kind: ContainerKind,
+        windows: Vec<Rc<RefCell<CompWindow>>>,
+        active: usize,
+        headers: Vec<(SolidColorBuffer, Point<i32, Logical>)>,
+        // windows of a loaded layout this container is still waiting for
+        placeholders: Vec<Swallow>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContainerKind {
+    // one row of tabs next to each other
+    Tabbed,
+    // one row per window, on top of each other
+    Stacked,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum HorizontalOrVertical {
     Horizontal,
     Vertical,
@@ -45,10 +78,10 @@ impl BinaryTree {
             BinaryTree::Empty => {
                 *self = BinaryTree::Window(window);
             }
-            BinaryTree::Window(w) => {
+            BinaryTree::Window(_) | BinaryTree::Placeholder(_) | BinaryTree::Container { .. } => {
                 let counter_rationew = 1.0f32 - rationew;
                 *self = BinaryTree::Split {
-                    left: Box::new(BinaryTree::Window(w.clone())),
+                    left: Box::new(self.clone()),
                     right: Box::new(BinaryTree::Window(window)),
                     split: splitnew,
                     ratio: rationew,
@@ -67,6 +100,57 @@ impl BinaryTree {
         }
     }
 
+    // Places `subtree` after the last leaf of the tree
+    pub fn append(&mut self, subtree: BinaryTree, splitnew: HorizontalOrVertical, rationew: f32) {
+        match self {
+            BinaryTree::Empty => *self = subtree,
+            BinaryTree::Split { right, .. } => right.append(subtree, splitnew, rationew),
+            _ => {
+                *self = BinaryTree::Split {
+                    left: Box::new(self.clone()),
+                    right: Box::new(subtree),
+                    split: splitnew,
+                    ratio: rationew,
+                    counter_ratio: 1.0f32 - rationew,
+                };
+            }
+        }
+    }
+
+    // Places `subtree` right after the leaf holding `window`, on the right-hand side
+    // so every `left` stays a leaf
+    fn insert_after(
+        &mut self,
+        window: &Window,
+        subtree: BinaryTree,
+        splitnew: HorizontalOrVertical,
+    ) {
+        match self {
+            BinaryTree::Split {
+                left, right, split, ..
+            } if left.contains(window) => {
+                let rest = std::mem::replace(right.as_mut(), BinaryTree::Empty);
+                **right = BinaryTree::Split {
+                    split: split.reverse(),
+                    ratio: 0.5,
+                    counter_ratio: 0.5,
+                    left: Box::new(subtree),
+                    right: Box::new(rest),
+                };
+            }
+            BinaryTree::Split { right, .. } => right.insert_after(window, subtree, splitnew),
+            _ => {
+                *self = BinaryTree::Split {
+                    split: splitnew,
+                    ratio: 0.5,
+                    counter_ratio: 0.5,
+                    left: Box::new(self.clone()),
+                    right: Box::new(subtree),
+                };
+            }
+        }
+    }
+
     pub fn remove(&mut self, window: &Window) {
         match self {
             BinaryTree::Empty => {}
@@ -76,6 +160,19 @@ impl BinaryTree {
                     *self = BinaryTree::Empty;
                 }
             }
+            BinaryTree::Placeholder(_) => {}
+            BinaryTree::Container {
+                windows,
+                active,
+                placeholders,
+                ..
+            } => {
+                windows.retain(|w| w.borrow().window != *window);
+                *active = (*active).min(windows.len().saturating_sub(1));
+                if windows.is_empty() && placeholders.is_empty() {
+                    *self = BinaryTree::Empty;
+                }
+            }
             BinaryTree::Split {
                 left,
                 right,
@@ -98,7 +195,29 @@ impl BinaryTree {
                 }
                 left.remove(window);
                 right.remove(window);
+                // a container losing its last window leaves an empty branch behind
+                if let BinaryTree::Empty = left.as_ref() {
+                    *self = *right.clone();
+                } else if let BinaryTree::Empty = right.as_ref() {
+                    *self = *left.clone();
+                }
+            }
+        }
+    }
+
+    pub fn swap_windows(
+        &mut self,
+        first: &Rc<RefCell<CompWindow>>,
+        second: &Rc<RefCell<CompWindow>>,
+    ) {
+        match self {
+            BinaryTree::Window(w) => swap_slots(std::slice::from_mut(w), first, second),
+            BinaryTree::Container { windows, .. } => swap_slots(windows, first, second),
+            BinaryTree::Split { left, right, .. } => {
+                left.swap_windows(first, second);
+                right.swap_windows(first, second);
             }
+            BinaryTree::Empty | BinaryTree::Placeholder(_) => {}
         }
     }
 
@@ -113,6 +232,8 @@ impl BinaryTree {
             },
             BinaryTree::Empty => {},
             BinaryTree::Window(_) => {},
+            BinaryTree::Placeholder(_) => {},
+            BinaryTree::Container { .. } => {},
         }
     }
 
@@ -120,6 +241,8 @@ impl BinaryTree {
         match self {
             BinaryTree::Empty => HorizontalOrVertical::Horizontal,
             BinaryTree::Window(_w) => HorizontalOrVertical::Horizontal,
+            BinaryTree::Placeholder(_) => HorizontalOrVertical::Horizontal,
+            BinaryTree::Container { .. } => HorizontalOrVertical::Horizontal,
             BinaryTree::Split {
                 left: _,
                 right,
@@ -153,6 +276,8 @@ impl BinaryTree {
         match self {
             BinaryTree::Empty => {}
             BinaryTree::Window(_) => {}
+            BinaryTree::Placeholder(_) => {}
+            BinaryTree::Container { .. } => {}
             BinaryTree::Split {
                 split: _,
                 ratio,
@@ -176,5 +301,494 @@ impl BinaryTree {
             }
         }
     }
+
+    pub fn contains(&self, window: &Window) -> bool {
+        match self {
+            BinaryTree::Empty => false,
+            BinaryTree::Placeholder(_) => false,
+            BinaryTree::Window(w) => w.borrow().window == *window,
+            BinaryTree::Container { windows, .. } => {
+                windows.iter().any(|w| w.borrow().window == *window)
+            }
+            BinaryTree::Split { left, right, .. } => {
+                left.contains(window) || right.contains(window)
+            }
+        }
+    }
+
+    fn windows(&self) -> Vec<Rc<RefCell<CompWindow>>> {
+        match self {
+            BinaryTree::Empty => Vec::new(),
+            BinaryTree::Placeholder(_) => Vec::new(),
+            BinaryTree::Window(w) => vec![w.clone()],
+            BinaryTree::Container { windows, .. } => windows.clone(),
+            BinaryTree::Split { left, right, .. } => {
+                let mut windows = left.windows();
+                windows.extend(right.windows());
+                windows
+            }
+        }
+    }
+
+    // Places a leaf of the tree inside `rec`.
+    // Containers keep their headers at the top and give the rest to every window they hold.
+    pub fn set_geometry(&mut self, rec: Rectangle<i32, Logical>) {
+        match self {
+            BinaryTree::Window(w) => w.borrow_mut().rec = rec,
+            BinaryTree::Container {
+                kind,
+                windows,
+                active,
+                headers,
+                ..
+            } => {
+                let (rows, split) = match kind {
+                    ContainerKind::Tabbed => (1, HorizontalOrVertical::Horizontal),
+                    ContainerKind::Stacked => (windows.len() as i32, HorizontalOrVertical::Vertical),
+                };
+                let header_height = (CONFIG.tab_height * rows).min(rec.size.h);
+                let header_area = Rectangle {
+                    loc: rec.loc,
+                    size: Size::from((rec.size.w, header_height)),
+                };
+
+                headers.resize_with(windows.len(), || {
+                    (SolidColorBuffer::new((0, 0), [0.0; 4]), Point::from((0, 0)))
+                });
+                for (index, ((buffer, loc), header)) in headers
+                    .iter_mut()
+                    .zip(split_rect(header_area, windows.len(), split))
+                    .enumerate()
+                {
+                    let color = if index == *active {
+                        CONFIG.active_tab_color
+                    } else {
+                        CONFIG.inactive_tab_color
+                    };
+                    // leave a pixel between headers so neighbouring tabs can be told apart
+                    let header = shrink(header, 1);
+                    buffer.update(header.size, color);
+                    *loc = header.loc;
+                }
+
+                let content = Rectangle {
+                    loc: Point::from((rec.loc.x, rec.loc.y + header_height)),
+                    size: Size::from((rec.size.w, rec.size.h - header_height)),
+                };
+                for window in windows {
+                    window.borrow_mut().rec = content;
+                }
+            }
+            // placeholders keep their tile empty until a window is swallowed
+            BinaryTree::Placeholder(_) => {}
+            BinaryTree::Empty => {}
+            BinaryTree::Split { .. } => {}
+        }
+    }
+
+    // The leaves of the tree in order, the tree always grows to the right
+    // so every `left` is a leaf.
+    fn leaves(&self) -> Vec<BinaryTree> {
+        match self {
+            BinaryTree::Empty => Vec::new(),
+            BinaryTree::Split { left, right, .. } => {
+                let mut leaves = vec![*left.clone()];
+                leaves.extend(right.leaves());
+                leaves
+            }
+            leaf => vec![leaf.clone()],
+        }
+    }
+
+    fn container_of(&mut self, window: &Window) -> Option<&mut BinaryTree> {
+        if matches!(self, BinaryTree::Container { .. }) && self.contains(window) {
+            return Some(self);
+        }
+        match self {
+            BinaryTree::Split { left, right, .. } => left
+                .container_of(window)
+                .or_else(|| right.container_of(window)),
+            _ => None,
+        }
+    }
+
+    fn is_window(&self, window: &Window) -> bool {
+        matches!(self, BinaryTree::Window(w) if w.borrow().window == *window)
+    }
+
+    // The split that has `window` as one of its two children
+    fn parent_of(&mut self, window: &Window) -> Option<&mut BinaryTree> {
+        let is_parent = match &*self {
+            BinaryTree::Split { left, right, .. } => {
+                left.is_window(window) || right.is_window(window)
+            }
+            _ => false,
+        };
+        if is_parent {
+            return Some(self);
+        }
+        match self {
+            BinaryTree::Split { left, right, .. } => {
+                left.parent_of(window).or_else(|| right.parent_of(window))
+            }
+            _ => None,
+        }
+    }
+
+    // Takes the first leaf out of the tree, its sibling takes the place of their split
+    fn take_first_leaf(&mut self) -> BinaryTree {
+        match self {
+            BinaryTree::Split { left, right, .. } => {
+                if let BinaryTree::Split { .. } = left.as_ref() {
+                    return left.take_first_leaf();
+                }
+                let leaf = std::mem::replace(left.as_mut(), BinaryTree::Empty);
+                *self = std::mem::replace(right.as_mut(), BinaryTree::Empty);
+                leaf
+            }
+            _ => std::mem::replace(self, BinaryTree::Empty),
+        }
+    }
+
+    // A container with the windows and placeholders of `leaves`, showing `window`
+    fn container(kind: ContainerKind, leaves: Vec<BinaryTree>, window: &Window) -> BinaryTree {
+        let mut windows = Vec::new();
+        let mut placeholders = Vec::new();
+        for leaf in leaves {
+            match leaf {
+                BinaryTree::Placeholder(swallow) => placeholders.push(swallow),
+                BinaryTree::Container {
+                    windows: w,
+                    placeholders: p,
+                    ..
+                } => {
+                    windows.extend(w);
+                    placeholders.extend(p);
+                }
+                leaf => windows.extend(leaf.windows()),
+            }
+        }
+        let active = windows
+            .iter()
+            .position(|w| w.borrow().window == *window)
+            .unwrap_or(0);
+        BinaryTree::Container {
+            kind,
+            windows,
+            active,
+            headers: Vec::new(),
+            placeholders,
+        }
+    }
+
+    // Groups `window` with the leaf next to it into a container, the rest of the tree
+    // keeps its splits. If the window already is in a container, only its kind is changed.
+    pub fn make_container(&mut self, window: &Window, kind: ContainerKind) {
+        if let Some(BinaryTree::Container { kind: old_kind, .. }) = self.container_of(window) {
+            *old_kind = kind;
+            return;
+        }
+        if self.is_window(window) {
+            // the only tile of the workspace
+            let leaf = std::mem::replace(self, BinaryTree::Empty);
+            *self = BinaryTree::container(kind, vec![leaf], window);
+            return;
+        }
+        let Some(parent) = self.parent_of(window) else {
+            return;
+        };
+        let BinaryTree::Split { left, right, .. } = &mut *parent else {
+            return;
+        };
+        if left.is_window(window) && matches!(right.as_ref(), BinaryTree::Split { .. }) {
+            // the neighbour is the first leaf of the right branch, the rest of it stays
+            let leaf = std::mem::replace(left.as_mut(), BinaryTree::Empty);
+            let next = right.take_first_leaf();
+            **left = BinaryTree::container(kind, vec![leaf, next], window);
+        } else {
+            // both children are leaves, so their split becomes the container
+            let leaves = vec![
+                std::mem::replace(left.as_mut(), BinaryTree::Empty),
+                std::mem::replace(right.as_mut(), BinaryTree::Empty),
+            ];
+            *parent = BinaryTree::container(kind, leaves, window);
+        }
+    }
+
+    // Turns the container holding `window` back into splits
+    pub fn split_container(&mut self, window: &Window) {
+        let Some(container) = self.container_of(window) else {
+            return;
+        };
+        let mut tree = BinaryTree::Empty;
+        for w in container.windows() {
+            let split = tree.next_split();
+            tree.insert(w, split, 0.5);
+        }
+        *container = tree;
+    }
+
+    // Activates the next (or previous) window of the container holding `window`
+    pub fn cycle_tab(&mut self, window: &Window, forward: bool) -> Option<Window> {
+        match self.container_of(window)? {
+            BinaryTree::Container {
+                windows, active, ..
+            } => {
+                let len = windows.len();
+                *active = if forward {
+                    (*active + 1) % len
+                } else {
+                    (*active + len - 1) % len
+                };
+                Some(windows[*active].borrow().window.clone())
+            }
+            _ => None,
+        }
+    }
+
+    pub fn activate_tab(&mut self, window: &Window) {
+        if let Some(BinaryTree::Container {
+            windows, active, ..
+        }) = self.container_of(window)
+        {
+            if let Some(index) = windows.iter().position(|w| w.borrow().window == *window) {
+                *active = index;
+            }
+        }
+    }
+
+    pub fn is_hidden(&self, window: &Window) -> bool {
+        match self {
+            BinaryTree::Container {
+                windows, active, ..
+            } => windows
+                .iter()
+                .enumerate()
+                .any(|(i, w)| i != *active && w.borrow().window == *window),
+            BinaryTree::Split { left, right, .. } => {
+                left.is_hidden(window) || right.is_hidden(window)
+            }
+            _ => false,
+        }
+    }
+
+    // Moves a tiled window into the closest container of the tree
+    pub fn move_into_container(&mut self, window: &Window) {
+        let leaves = self.leaves();
+        let Some(index) = leaves.iter().position(|l| l.is_window(window)) else {
+            return;
+        };
+        // containers are found again through one of their windows once `window` is removed
+        let Some(anchor) = leaves
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| matches!(l, BinaryTree::Container { .. }))
+            .filter_map(|(i, l)| Some((i, l.windows().first()?.borrow().window.clone())))
+            .min_by_key(|(i, _)| i.abs_diff(index))
+            .map(|(_, anchor)| anchor)
+        else {
+            return;
+        };
+        let Some(moved) = leaves[index].windows().pop() else {
+            return;
+        };
+        self.remove(window);
+        if let Some(BinaryTree::Container {
+            windows, active, ..
+        }) = self.container_of(&anchor)
+        {
+            windows.push(moved);
+            *active = windows.len() - 1;
+        }
+    }
+
+    // Takes `window` out of its container and tiles it right after the container
+    pub fn move_out_of_container(&mut self, window: &Window) {
+        let Some(container) = self.container_of(window) else {
+            return;
+        };
+        let BinaryTree::Container {
+            windows,
+            placeholders,
+            ..
+        } = &*container
+        else {
+            return;
+        };
+        let Some(moved) = windows.iter().find(|w| w.borrow().window == *window).cloned() else {
+            return;
+        };
+        if windows.len() == 1 && placeholders.is_empty() {
+            *container = BinaryTree::Window(moved);
+            return;
+        }
+        // a container at the end of the tree is split along its longer side
+        let rec = moved.borrow().rec;
+        let split = if rec.size.w >= rec.size.h {
+            HorizontalOrVertical::Horizontal
+        } else {
+            HorizontalOrVertical::Vertical
+        };
+        // the container is still found through `window` until it is taken out of it
+        self.insert_after(window, BinaryTree::Window(moved), split);
+        if let Some(container) = self.container_of(window) {
+            container.remove(window);
+        }
+    }
+
+    // Puts `window` in place of the first placeholder waiting for it
+    pub fn swallow(&mut self, window: &Rc<RefCell<CompWindow>>) -> bool {
+        match self {
+            BinaryTree::Placeholder(swallow) => {
+                if swallow.matches(&window.borrow().window) {
+                    *self = BinaryTree::Window(window.clone());
+                    true
+                } else {
+                    false
+                }
+            }
+            BinaryTree::Container {
+                windows,
+                active,
+                placeholders,
+                ..
+            } => {
+                let index = placeholders
+                    .iter()
+                    .position(|p| p.matches(&window.borrow().window));
+                if let Some(index) = index {
+                    placeholders.remove(index);
+                    windows.push(window.clone());
+                    *active = windows.len() - 1;
+                    true
+                } else {
+                    false
+                }
+            }
+            BinaryTree::Split { left, right, .. } => left.swallow(window) || right.swallow(window),
+            _ => false,
+        }
+    }
+
+    pub fn has_placeholders(&self) -> bool {
+        match self {
+            BinaryTree::Placeholder(_) => true,
+            BinaryTree::Container { placeholders, .. } => !placeholders.is_empty(),
+            BinaryTree::Split { left, right, .. } => {
+                left.has_placeholders() || right.has_placeholders()
+            }
+            _ => false,
+        }
+    }
+
+    pub fn wants(&self, window: &Window) -> bool {
+        match self {
+            BinaryTree::Placeholder(swallow) => swallow.matches(window),
+            BinaryTree::Container { placeholders, .. } => {
+                placeholders.iter().any(|p| p.matches(window))
+            }
+            BinaryTree::Split { left, right, .. } => left.wants(window) || right.wants(window),
+            _ => false,
+        }
+    }
+
+    // Loads a saved tree, appending it to the windows already tiled
+    pub fn load(&mut self, tree: SavedTree) {
+        let loaded = tree.into_tree();
+        let split = self.next_split();
+        self.append(loaded, split, 0.5);
+    }
+
+    pub fn decorations(&self) -> Vec<(SolidColorBuffer, Point<i32, Logical>)> {
+        match self {
+            BinaryTree::Container { headers, .. } => headers.clone(),
+            BinaryTree::Split { left, right, .. } => {
+                let mut decorations = left.decorations();
+                decorations.extend(right.decorations());
+                decorations
+            }
+            _ => Vec::new(),
+        }
+    }
 }
 
+impl Layout for BinaryTree {
+    fn kind(&self) -> LayoutKind {
+        LayoutKind::Bsp
+    }
+
+    fn add_window(&mut self, window: Rc<RefCell<CompWindow>>) {
+        if self.swallow(&window) {
+            return;
+        }
+        let split = self.next_split();
+        self.insert(window, split, 0.5);
+    }
+
+    fn remove_window(&mut self, window: &Window) {
+        self.remove(window);
+        // to fix windows horizontal-vertical division layout
+        self.update_after_removal(HorizontalOrVertical::Horizontal);
+    }
+
+    fn swap_windows(&mut self, first: &Rc<RefCell<CompWindow>>, second: &Rc<RefCell<CompWindow>>) {
+        BinaryTree::swap_windows(self, first, second)
+    }
+
+    fn arrange(&mut self, region: Rectangle<i32, Logical>, gaps: (i32, i32)) {
+        bsp_arrange(self, region, gaps);
+    }
+
+    fn resize(&mut self, update_interval: f32, increment: bool) {
+        self.update_ratio(update_interval, Some(increment));
+    }
+
+    // tabs of a container share its rectangle, so nothing moves
+    fn focus(&mut self, window: &Window) -> bool {
+        self.activate_tab(window);
+        false
+    }
+
+    fn is_visible(&self, window: &Window) -> bool {
+        !self.is_hidden(window)
+    }
+
+    fn set_container(&mut self, window: &Window, kind: Option<ContainerKind>) {
+        match kind {
+            Some(kind) => self.make_container(window, kind),
+            None => self.split_container(window),
+        }
+    }
+
+    fn cycle_tab(&mut self, window: &Window, forward: bool) -> Option<Window> {
+        BinaryTree::cycle_tab(self, window, forward)
+    }
+
+    fn move_into_container(&mut self, window: &Window) {
+        BinaryTree::move_into_container(self, window)
+    }
+
+    fn move_out_of_container(&mut self, window: &Window) {
+        BinaryTree::move_out_of_container(self, window)
+    }
+
+    fn decorations(&self) -> Vec<(SolidColorBuffer, Point<i32, Logical>)> {
+        BinaryTree::decorations(self)
+    }
+
+    fn wants(&self, window: &Window) -> bool {
+        BinaryTree::wants(self, window)
+    }
+
+    fn has_placeholders(&self) -> bool {
+        BinaryTree::has_placeholders(self)
+    }
+
+    fn save(&self) -> Option<SavedTree> {
+        SavedTree::from_tree(self)
+    }
+
+    fn load(&mut self, tree: SavedTree) {
+        BinaryTree::load(self, tree)
+    }
+}
@@ -0,0 +1,77 @@
+use smithay::utils::{Logical, Point};
+
+use crate::{
+    config::{GestureKind, GesturePattern},
+    state::CONFIG,
+};
+
+// What the compositor does with a touchpad gesture
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GestureTarget {
+    // the shown workspace follows the fingers
+    Workspace,
+    // a binding of `CONFIG.gesture_bindings` runs once the gesture ends
+    Binding,
+    // forwarded with the pointer-gestures protocol
+    Client,
+}
+
+// A touchpad gesture in progress
+#[derive(Debug)]
+pub struct Gesture {
+    pub pinch: bool,
+    pub fingers: u32,
+    pub target: GestureTarget,
+    // motion of the fingers since the gesture began
+    pub delta: Point<f64, Logical>,
+    // distance between the fingers relative to the beginning, for pinches
+    pub scale: f64,
+}
+
+impl Gesture {
+    pub fn new(pinch: bool, fingers: u32) -> Self {
+        let bound = CONFIG
+            .gesture_bindings
+            .keys()
+            .any(|pattern| pattern.fingers == fingers && pattern.kind.is_pinch() == pinch);
+        let target = if !pinch && fingers == CONFIG.workspace_swipe_fingers {
+            GestureTarget::Workspace
+        } else if bound {
+            GestureTarget::Binding
+        } else {
+            GestureTarget::Client
+        };
+        Gesture {
+            pinch,
+            fingers,
+            target,
+            delta: Point::from((0.0, 0.0)),
+            scale: 1.0,
+        }
+    }
+
+    // The pattern of the gesture by the direction the fingers moved the most in
+    pub fn pattern(&self) -> GesturePattern {
+        let kind = if self.pinch {
+            if self.scale < 1.0 {
+                GestureKind::PinchIn
+            } else {
+                GestureKind::PinchOut
+            }
+        } else if self.delta.x.abs() > self.delta.y.abs() {
+            if self.delta.x < 0.0 {
+                GestureKind::SwipeLeft
+            } else {
+                GestureKind::SwipeRight
+            }
+        } else if self.delta.y < 0.0 {
+            GestureKind::SwipeUp
+        } else {
+            GestureKind::SwipeDown
+        };
+        GesturePattern {
+            fingers: self.fingers,
+            kind,
+        }
+    }
+}
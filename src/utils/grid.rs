@@ -0,0 +1,78 @@
+use std::{cell::RefCell, rc::Rc};
+
+use smithay::{
+    desktop::Window,
+    utils::{Logical, Rectangle},
+};
+
+use crate::config::LayoutKind;
+
+use super::{
+    binarytree::HorizontalOrVertical,
+    layout::{swap_slots, Layout},
+    tiling::{shrink, split_rect},
+    workspace::CompWindow,
+};
+
+// Windows are placed in rows of an (almost) square grid,
+// the last row stretches its windows to fill the width.
+pub struct Grid {
+    windows: Vec<Rc<RefCell<CompWindow>>>,
+}
+
+impl Grid {
+    pub fn new() -> Self {
+        Grid {
+            windows: Vec::new(),
+        }
+    }
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Layout for Grid {
+    fn kind(&self) -> LayoutKind {
+        LayoutKind::Grid
+    }
+
+    fn add_window(&mut self, window: Rc<RefCell<CompWindow>>) {
+        self.windows.push(window);
+    }
+
+    fn remove_window(&mut self, window: &Window) {
+        self.windows.retain(|w| &w.borrow().window != window);
+    }
+
+    fn swap_windows(&mut self, first: &Rc<RefCell<CompWindow>>, second: &Rc<RefCell<CompWindow>>) {
+        swap_slots(&mut self.windows, first, second);
+    }
+
+    fn arrange(&mut self, region: Rectangle<i32, Logical>, gaps: (i32, i32)) {
+        let count = self.windows.len();
+        if count == 0 {
+            return;
+        }
+        let region = shrink(region, gaps.0);
+        let columns = (count as f32).sqrt().ceil() as usize;
+        let rows = count.div_ceil(columns);
+
+        let mut windows = self.windows.iter();
+        for (row, row_rec) in split_rect(region, rows, HorizontalOrVertical::Vertical)
+            .into_iter()
+            .enumerate()
+        {
+            let in_row = columns.min(count - row * columns);
+            for rec in split_rect(row_rec, in_row, HorizontalOrVertical::Horizontal) {
+                if let Some(window) = windows.next() {
+                    window.borrow_mut().rec = shrink(rec, gaps.1);
+                }
+            }
+        }
+    }
+
+    fn resize(&mut self, _update_interval: f32, _increment: bool) {}
+}
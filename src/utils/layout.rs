@@ -0,0 +1,121 @@
+use std::{cell::RefCell, rc::Rc};
+
+use smithay::{
+    backend::renderer::element::solid::SolidColorBuffer,
+    desktop::Window,
+    utils::{Logical, Point, Rectangle},
+};
+
+use crate::{config::LayoutKind, state::CONFIG};
+
+use super::{
+    binarytree::{BinaryTree, ContainerKind},
+    grid::Grid, masterstack::MasterStack, monocle::Monocle,
+    saved_layout::SavedTree,
+    scrolling::Scrolling,
+    workspace::CompWindow,
+};
+
+/// A tiling algorithm deciding where the windows of a workspace go.
+///
+/// Layouts keep their own references to the windows they manage and write
+/// the computed geometry into `CompWindow::rec` on `arrange`.
+pub trait Layout {
+    fn kind(&self) -> LayoutKind;
+
+    fn add_window(&mut self, window: Rc<RefCell<CompWindow>>);
+
+    fn remove_window(&mut self, window: &Window);
+
+    /// Puts `first` into the slot of `second` and the other way round, for whichever of
+    /// the two the layout holds. No tile moves.
+    fn swap_windows(&mut self, first: &Rc<RefCell<CompWindow>>, second: &Rc<RefCell<CompWindow>>);
+
+    /// Computes the rectangle of every window inside `region`.
+    /// `gaps` is `(outer, inner)`, like `CONFIG.gaps`.
+    fn arrange(&mut self, region: Rectangle<i32, Logical>, gaps: (i32, i32));
+
+    /// Grows (`increment == true`) or shrinks the main area of the layout.
+    fn resize(&mut self, update_interval: f32, increment: bool);
+
+    fn change_master_count(&mut self, _delta: i32) {}
+
+    /// Switches the size of the focused tile to the next preset.
+    fn cycle_width(&mut self) {}
+
+    /// Moves `window` one step towards the end (`forward == true`) or the start of the layout.
+    fn move_window(&mut self, _window: &Window, _forward: bool) {}
+
+    /// Called whenever `window` receives keyboard focus.
+    /// Returns whether the windows have to be arranged again.
+    fn focus(&mut self, _window: &Window) -> bool {
+        false
+    }
+
+    /// Whether `window` should be rendered and receive pointer input.
+    fn is_visible(&self, _window: &Window) -> bool {
+        true
+    }
+
+    /// Groups `window` with its siblings into a tabbed or stacked container,
+    /// `None` turns its container back into splits.
+    fn set_container(&mut self, _window: &Window, _kind: Option<ContainerKind>) {}
+
+    /// Shows the next (or previous) window of the container holding `window` and returns it.
+    fn cycle_tab(&mut self, _window: &Window, _forward: bool) -> Option<Window> {
+        None
+    }
+
+    fn move_into_container(&mut self, _window: &Window) {}
+
+    fn move_out_of_container(&mut self, _window: &Window) {}
+
+    /// Extra elements drawn by the layout itself, like tab headers.
+    fn decorations(&self) -> Vec<(SolidColorBuffer, Point<i32, Logical>)> {
+        Vec::new()
+    }
+
+    /// Whether a placeholder of a loaded layout is waiting for `window`.
+    fn wants(&self, _window: &Window) -> bool {
+        false
+    }
+
+    /// Whether placeholders of a loaded layout are still waiting for windows.
+    fn has_placeholders(&self) -> bool {
+        false
+    }
+
+    /// The shape of the layout to be written to disk, if it supports persisting.
+    fn save(&self) -> Option<SavedTree> {
+        None
+    }
+
+    fn load(&mut self, _tree: SavedTree) {}
+}
+
+// Exchanges `first` and `second` wherever they are in `windows`, see `Layout::swap_windows`
+pub fn swap_slots(
+    windows: &mut [Rc<RefCell<CompWindow>>],
+    first: &Rc<RefCell<CompWindow>>,
+    second: &Rc<RefCell<CompWindow>>,
+) {
+    for slot in windows {
+        if Rc::ptr_eq(slot, first) {
+            *slot = second.clone();
+        } else if Rc::ptr_eq(slot, second) {
+            *slot = first.clone();
+        }
+    }
+}
+
+pub fn new_layout(kind: LayoutKind) -> Box<dyn Layout> {
+    match kind {
+        LayoutKind::Bsp => Box::new(BinaryTree::new()),
+        LayoutKind::MasterStack => {
+            Box::new(MasterStack::new(CONFIG.master_count, CONFIG.master_factor))
+        }
+        LayoutKind::Monocle => Box::new(Monocle::new()),
+        LayoutKind::Grid => Box::new(Grid::new()),
+        LayoutKind::Scrolling => Box::new(Scrolling::new()),
+    }
+}
@@ -0,0 +1,97 @@
+use std::{cell::RefCell, rc::Rc};
+
+use smithay::{
+    desktop::Window,
+    utils::{Logical, Point, Rectangle, Size},
+};
+
+use crate::config::LayoutKind;
+
+use super::{
+    binarytree::HorizontalOrVertical,
+    layout::{swap_slots, Layout},
+    tiling::{shrink, split_rect},
+    workspace::CompWindow,
+};
+
+// dwm-style layout: the first `master_count` windows share the master column on the left,
+// the rest are stacked on top of each other on the right.
+pub struct MasterStack {
+    windows: Vec<Rc<RefCell<CompWindow>>>,
+    master_count: usize,
+    master_factor: f32,
+}
+
+impl MasterStack {
+    pub fn new(master_count: usize, master_factor: f32) -> Self {
+        MasterStack {
+            windows: Vec::new(),
+            master_count,
+            master_factor,
+        }
+    }
+}
+
+impl Layout for MasterStack {
+    fn kind(&self) -> LayoutKind {
+        LayoutKind::MasterStack
+    }
+
+    fn add_window(&mut self, window: Rc<RefCell<CompWindow>>) {
+        self.windows.push(window);
+    }
+
+    fn remove_window(&mut self, window: &Window) {
+        self.windows.retain(|w| &w.borrow().window != window);
+    }
+
+    fn swap_windows(&mut self, first: &Rc<RefCell<CompWindow>>, second: &Rc<RefCell<CompWindow>>) {
+        swap_slots(&mut self.windows, first, second);
+    }
+
+    fn arrange(&mut self, region: Rectangle<i32, Logical>, gaps: (i32, i32)) {
+        let region = shrink(region, gaps.0);
+        let masters = self.master_count.min(self.windows.len());
+        let stacked = self.windows.len() - masters;
+
+        // the master column takes the whole area if one of the columns is empty
+        let master_width = if masters == 0 {
+            0
+        } else if stacked == 0 {
+            region.size.w
+        } else {
+            (region.size.w as f32 * self.master_factor) as i32
+        };
+        let master_area = Rectangle {
+            loc: region.loc,
+            size: Size::from((master_width, region.size.h)),
+        };
+        let stack_area = Rectangle {
+            loc: Point::from((region.loc.x + master_width, region.loc.y)),
+            size: Size::from((region.size.w - master_width, region.size.h)),
+        };
+
+        let recs = split_rect(master_area, masters, HorizontalOrVertical::Vertical)
+            .into_iter()
+            .chain(split_rect(
+                stack_area,
+                stacked,
+                HorizontalOrVertical::Vertical,
+            ));
+        for (window, rec) in self.windows.iter().zip(recs) {
+            window.borrow_mut().rec = shrink(rec, gaps.1);
+        }
+    }
+
+    fn resize(&mut self, update_interval: f32, increment: bool) {
+        if increment {
+            self.master_factor = f32::min(self.master_factor + update_interval, 0.9);
+        } else {
+            self.master_factor = f32::max(self.master_factor - update_interval, 0.1);
+        }
+    }
+
+    fn change_master_count(&mut self, delta: i32) {
+        self.master_count = (self.master_count as i32 + delta).max(0) as usize;
+    }
+}
@@ -1,5 +1,15 @@
 pub mod binarytree;
 pub mod focus;
+pub mod gesture;
+pub mod grid;
+pub mod layout;
+pub mod masterstack;
+pub mod monocle;
 pub mod render;
+pub mod saved_layout;
+pub mod scrolling;
+pub mod status;
+pub mod switcher;
+pub mod tablet;
 pub mod tiling;
 pub mod workspace;
\ No newline at end of file
@@ -0,0 +1,85 @@
+use std::{cell::RefCell, rc::Rc};
+
+use smithay::{
+    desktop::Window,
+    utils::{Logical, Rectangle},
+};
+
+use crate::config::LayoutKind;
+
+use super::{
+    layout::{swap_slots, Layout},
+    tiling::shrink,
+    workspace::CompWindow,
+};
+
+// Every window takes the whole output, only the focused one is shown.
+pub struct Monocle {
+    windows: Vec<Rc<RefCell<CompWindow>>>,
+    active: Option<Window>,
+}
+
+impl Monocle {
+    pub fn new() -> Self {
+        Monocle {
+            windows: Vec::new(),
+            active: None,
+        }
+    }
+}
+
+impl Default for Monocle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Layout for Monocle {
+    fn kind(&self) -> LayoutKind {
+        LayoutKind::Monocle
+    }
+
+    fn add_window(&mut self, window: Rc<RefCell<CompWindow>>) {
+        self.active = Some(window.borrow().window.clone());
+        self.windows.push(window);
+    }
+
+    fn remove_window(&mut self, window: &Window) {
+        self.windows.retain(|w| &w.borrow().window != window);
+        if self.active.as_ref() == Some(window) {
+            self.active = self.windows.last().map(|w| w.borrow().window.clone());
+        }
+    }
+
+    // the window taking over the slot of the shown one is shown instead
+    fn swap_windows(&mut self, first: &Rc<RefCell<CompWindow>>, second: &Rc<RefCell<CompWindow>>) {
+        swap_slots(&mut self.windows, first, second);
+        let (first, second) = (first.borrow().window.clone(), second.borrow().window.clone());
+        if self.active.as_ref() == Some(&first) {
+            self.active = Some(second);
+        } else if self.active.as_ref() == Some(&second) {
+            self.active = Some(first);
+        }
+    }
+
+    fn arrange(&mut self, region: Rectangle<i32, Logical>, gaps: (i32, i32)) {
+        let rec = shrink(region, gaps.0 + gaps.1);
+        for window in &self.windows {
+            window.borrow_mut().rec = rec;
+        }
+    }
+
+    fn resize(&mut self, _update_interval: f32, _increment: bool) {}
+
+    // every window already has the whole output, so nothing moves
+    fn focus(&mut self, window: &Window) -> bool {
+        if self.windows.iter().any(|w| &w.borrow().window == window) {
+            self.active = Some(window.clone());
+        }
+        false
+    }
+
+    fn is_visible(&self, window: &Window) -> bool {
+        self.active.as_ref() == Some(window)
+    }
+}
@@ -1,9 +1,19 @@
 use smithay::{
     backend::renderer::{
-        element::{surface::WaylandSurfaceRenderElement, texture::TextureRenderElement},
-        ImportAll, ImportMem, Renderer,
+        element::{
+            solid::{SolidColorBuffer, SolidColorRenderElement},
+            surface::WaylandSurfaceRenderElement,
+            texture::TextureRenderElement,
+            AsRenderElements, Element, Id, RenderElement,
+        },
+        utils::{with_renderer_surface_state, CommitCounter},
+        Frame, ImportAll, ImportMem, Renderer, Texture,
     },
+    desktop::Window,
+    reexports::wayland_server::protocol::wl_surface::WlSurface,
     render_elements,
+    utils::{Buffer, Logical, Physical, Point, Rectangle, Scale, Transform},
+    wayland::compositor::{with_surface_tree_downward, TraversalAction},
 };
 
 render_elements! {
@@ -11,4 +21,177 @@ render_elements! {
         R: ImportAll + ImportMem;
     Texture=TextureRenderElement<<R as Renderer>::TextureId>,
     Surface=WaylandSurfaceRenderElement<R>,
+    Solid=SolidColorRenderElement,
+    Opacity=OpacityRenderElement<R>,
+}
+
+// A surface of a window drawn with the opacity of its window rule
+pub struct OpacityRenderElement<R: Renderer> {
+    inner: WaylandSurfaceRenderElement<R>,
+    surface: WlSurface,
+    renderer_id: usize,
+    alpha: f32,
+}
+
+impl<R> Element for OpacityRenderElement<R>
+where
+    R: Renderer + ImportAll,
+    <R as Renderer>::TextureId: Texture + 'static,
+{
+    fn id(&self) -> &Id {
+        self.inner.id()
+    }
+
+    fn current_commit(&self) -> CommitCounter {
+        self.inner.current_commit()
+    }
+
+    fn src(&self) -> Rectangle<f64, Buffer> {
+        self.inner.src()
+    }
+
+    fn geometry(&self, scale: Scale<f64>) -> Rectangle<i32, Physical> {
+        self.inner.geometry(scale)
+    }
+
+    fn transform(&self) -> Transform {
+        self.inner.transform()
+    }
+
+    fn damage_since(
+        &self,
+        scale: Scale<f64>,
+        commit: Option<CommitCounter>,
+    ) -> Vec<Rectangle<i32, Physical>> {
+        self.inner.damage_since(scale, commit)
+    }
+
+    // whatever is below a translucent surface stays visible
+    fn opaque_regions(&self, _scale: Scale<f64>) -> Vec<Rectangle<i32, Physical>> {
+        Vec::new()
+    }
+}
+
+impl<R> RenderElement<R> for OpacityRenderElement<R>
+where
+    R: Renderer + ImportAll,
+    <R as Renderer>::TextureId: Texture + 'static,
+{
+    fn draw<'a>(
+        &self,
+        frame: &mut <R as Renderer>::Frame<'a>,
+        src: Rectangle<f64, Buffer>,
+        dst: Rectangle<i32, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+    ) -> Result<(), R::Error> {
+        with_renderer_surface_state(&self.surface, |state| {
+            match state.texture::<R>(self.renderer_id) {
+                Some(texture) => frame.render_texture_from_to(
+                    texture,
+                    src,
+                    dst,
+                    damage,
+                    self.inner.transform(),
+                    self.alpha,
+                ),
+                None => Ok(()),
+            }
+        })
+    }
+}
+
+// The elements of `window` with the surfaces of the toplevel drawn at `alpha`,
+// its popups stay opaque.
+pub fn translucent_window_elements<R, C>(
+    renderer: &mut R,
+    window: &Window,
+    location: Point<i32, Physical>,
+    scale: f64,
+    alpha: f32,
+) -> Vec<C>
+where
+    R: Renderer + ImportAll,
+    <R as Renderer>::TextureId: Texture + 'static,
+    C: From<WaylandSurfaceRenderElement<R>> + From<OpacityRenderElement<R>>,
+{
+    let mut surfaces = Vec::new();
+    with_surface_tree_downward(
+        window.toplevel().wl_surface(),
+        (),
+        |_, _, _| TraversalAction::DoChildren(()),
+        |surface, _, _| surfaces.push(surface.clone()),
+        |_, _, _| true,
+    );
+    let renderer_id = renderer.id();
+    AsRenderElements::<R>::render_elements::<WaylandSurfaceRenderElement<R>>(
+        window,
+        renderer,
+        location,
+        Scale::from(scale),
+    )
+    .into_iter()
+    .map(|element| {
+        let surface = surfaces
+            .iter()
+            .find(|s| Id::from_wayland_resource(*s) == *element.id());
+        match surface {
+            Some(surface) => OpacityRenderElement {
+                inner: element,
+                surface: surface.clone(),
+                renderer_id,
+                alpha,
+            }
+            .into(),
+            None => element.into(),
+        }
+    })
+    .collect()
+}
+
+// Border drawn around a window, outside of its geometry
+#[derive(Debug, Clone, Default)]
+pub struct Border {
+    // top, bottom, left and right edge
+    edges: Vec<(SolidColorBuffer, Point<i32, Logical>)>,
+    // what the edges were last placed around, the buffers only change with it
+    rec: Rectangle<i32, Logical>,
+    width: i32,
+    color: [f32; 4],
+}
+
+// the border is only render state, it doesn't tell windows apart
+impl PartialEq for Border {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Border {
+    // Places the edges around `rec` and returns them
+    pub fn update(
+        &mut self,
+        rec: Rectangle<i32, Logical>,
+        width: i32,
+        color: [f32; 4],
+    ) -> &[(SolidColorBuffer, Point<i32, Logical>)] {
+        if !self.edges.is_empty() && (self.rec, self.width, self.color) == (rec, width, color) {
+            return &self.edges;
+        }
+        let (x, y, w, h) = (rec.loc.x, rec.loc.y, rec.size.w, rec.size.h);
+        let edges = [
+            Rectangle::from_loc_and_size((x - width, y - width), (w + 2 * width, width)),
+            Rectangle::from_loc_and_size((x - width, y + h), (w + 2 * width, width)),
+            Rectangle::from_loc_and_size((x - width, y), (width, h)),
+            Rectangle::from_loc_and_size((x + w, y), (width, h)),
+        ];
+        self.edges.resize_with(edges.len(), || {
+            (SolidColorBuffer::new((0, 0), [0.0; 4]), Point::from((0, 0)))
+        });
+        for ((buffer, loc), edge) in self.edges.iter_mut().zip(edges) {
+            buffer.update(edge.size, color);
+            *loc = edge.loc;
+        }
+        (self.rec, self.width, self.color) = (rec, width, color);
+        &self.edges
+    }
 }
@@ -0,0 +1,204 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use smithay::desktop::Window;
+
+use crate::state::CONFIG;
+
+use super::{
+    binarytree::{BinaryTree, ContainerKind, HorizontalOrVertical},
+    workspace::window_identity,
+};
+
+/// Criteria a new window has to match to be swallowed by a placeholder,
+/// unset fields match any window.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Swallow {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+impl Swallow {
+    // Titles tend to change between sessions, so they are only used when there is no app_id
+    pub fn from_window(window: &Window) -> Self {
+        let (app_id, title) = window_identity(window);
+        match app_id {
+            Some(app_id) => Swallow {
+                app_id: Some(app_id),
+                title: None,
+            },
+            None => Swallow {
+                app_id: None,
+                title,
+            },
+        }
+    }
+
+    // A swallow without criteria never matches, its placeholder would wait forever
+    pub fn is_empty(&self) -> bool {
+        self.app_id.is_none() && self.title.is_none()
+    }
+
+    pub fn matches(&self, window: &Window) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        let (app_id, title) = window_identity(window);
+        self.app_id.as_ref().is_none_or(|a| Some(a) == app_id.as_ref())
+            && self.title.as_ref().is_none_or(|t| Some(t) == title.as_ref())
+    }
+}
+
+/// The shape of a `BinaryTree` as it is written to disk,
+/// every window is replaced by a placeholder.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SavedTree {
+    Placeholder(Swallow),
+    Container {
+        kind: ContainerKind,
+        windows: Vec<Swallow>,
+    },
+    Split {
+        split: HorizontalOrVertical,
+        ratio: f32,
+        left: Box<SavedTree>,
+        right: Box<SavedTree>,
+    },
+}
+
+impl SavedTree {
+    pub fn from_tree(tree: &BinaryTree) -> Option<Self> {
+        match tree {
+            BinaryTree::Empty => None,
+            BinaryTree::Window(w) => Some(SavedTree::Placeholder(Swallow::from_window(
+                &w.borrow().window,
+            ))),
+            BinaryTree::Placeholder(swallow) => Some(SavedTree::Placeholder(swallow.clone())),
+            BinaryTree::Container {
+                kind,
+                windows,
+                placeholders,
+                ..
+            } => Some(SavedTree::Container {
+                kind: *kind,
+                windows: windows
+                    .iter()
+                    .map(|w| Swallow::from_window(&w.borrow().window))
+                    .chain(placeholders.iter().cloned())
+                    .collect(),
+            }),
+            BinaryTree::Split {
+                split,
+                ratio,
+                left,
+                right,
+                ..
+            } => match (Self::from_tree(left), Self::from_tree(right)) {
+                (Some(left), Some(right)) => Some(SavedTree::Split {
+                    split: *split,
+                    ratio: *ratio,
+                    left: Box::new(left),
+                    right: Box::new(right),
+                }),
+                (left, right) => left.or(right),
+            },
+        }
+    }
+
+    fn has_empty_swallow(&self) -> bool {
+        match self {
+            SavedTree::Placeholder(swallow) => swallow.is_empty(),
+            SavedTree::Container { windows, .. } => windows.iter().any(Swallow::is_empty),
+            SavedTree::Split { left, right, .. } => {
+                left.has_empty_swallow() || right.has_empty_swallow()
+            }
+        }
+    }
+
+    // Splits nested on the left are flattened onto the right-hand side,
+    // the layouts expect every `left` to be a leaf
+    pub fn into_tree(self) -> BinaryTree {
+        match self {
+            SavedTree::Placeholder(swallow) => BinaryTree::Placeholder(swallow),
+            SavedTree::Container { kind, windows } => BinaryTree::Container {
+                kind,
+                windows: Vec::new(),
+                active: 0,
+                headers: Vec::new(),
+                placeholders: windows,
+            },
+            SavedTree::Split {
+                split,
+                ratio,
+                left,
+                right,
+            } => {
+                let mut tree = left.into_tree();
+                let right = right.into_tree();
+                if let BinaryTree::Split { .. } = tree {
+                    tree.append(right, split, ratio);
+                    tree
+                } else {
+                    BinaryTree::Split {
+                        split,
+                        ratio,
+                        counter_ratio: 1.0f32 - ratio,
+                        left: Box::new(tree),
+                        right: Box::new(right),
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Layouts persisted by `Config::persist_layouts`, one per workspace. They are kept apart
+// from the layouts saved with `Action::SaveLayout` in `Config::layout_dir`.
+pub fn session_dir() -> PathBuf {
+    CONFIG.layout_dir.join("session")
+}
+
+fn layout_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}.json", name))
+}
+
+pub fn save(dir: &Path, name: &str, tree: &SavedTree) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let json = serde_json::to_string_pretty(tree)?;
+    fs::write(layout_path(dir, name), json)
+}
+
+pub fn load(dir: &Path, name: &str) -> io::Result<SavedTree> {
+    let json = fs::read_to_string(layout_path(dir, name))?;
+    let tree: SavedTree = serde_json::from_str(&json)?;
+    if tree.has_empty_swallow() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "placeholder without app_id or title",
+        ));
+    }
+    Ok(tree)
+}
+
+// Names of all layouts saved in `dir`
+pub fn names(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| path.file_stem().and_then(|s| s.to_str()).map(String::from))
+        .collect()
+}
+
+pub fn forget(dir: &Path, name: &str) {
+    let _ = fs::remove_file(layout_path(dir, name));
+}
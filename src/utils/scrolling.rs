@@ -0,0 +1,237 @@
+use std::{cell::RefCell, rc::Rc};
+
+use smithay::{
+    desktop::Window,
+    utils::{Logical, Point, Rectangle, Size},
+};
+
+use crate::{config::LayoutKind, state::CONFIG};
+
+use super::{
+    binarytree::HorizontalOrVertical,
+    layout::{swap_slots, Layout},
+    tiling::{shrink, split_rect},
+    workspace::CompWindow,
+};
+
+struct Column {
+    windows: Vec<Rc<RefCell<CompWindow>>>,
+    // index into `CONFIG.column_widths`
+    width: usize,
+}
+
+impl Column {
+    fn contains(&self, window: &Window) -> bool {
+        self.windows.iter().any(|w| &w.borrow().window == window)
+    }
+
+    fn width_on(&self, output_width: i32) -> i32 {
+        let factor = CONFIG.column_widths.get(self.width).copied().unwrap_or(1.0);
+        (output_width as f32 * factor) as i32
+    }
+}
+
+// niri/PaperWM style layout: windows live in columns on an infinite horizontal strip
+// and the output is a viewport that scrolls to keep the active column visible.
+pub struct Scrolling {
+    columns: Vec<Column>,
+    active: usize,
+    // horizontal position of the viewport on the strip
+    view_offset: i32,
+    // width of the viewport at the last arrange
+    view_width: i32,
+}
+
+impl Scrolling {
+    pub fn new() -> Self {
+        Scrolling {
+            columns: Vec::new(),
+            active: 0,
+            view_offset: 0,
+            view_width: 0,
+        }
+    }
+
+    fn column_of(&self, window: &Window) -> Option<usize> {
+        self.columns.iter().position(|c| c.contains(window))
+    }
+
+    // Start and end of the column `index` on the strip, as of the last arrange
+    fn column_span(&self, index: usize) -> (i32, i32) {
+        let start: i32 = self.columns[..index]
+            .iter()
+            .map(|c| c.width_on(self.view_width))
+            .sum();
+        (start, start + self.columns[index].width_on(self.view_width))
+    }
+}
+
+impl Default for Scrolling {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Layout for Scrolling {
+    fn kind(&self) -> LayoutKind {
+        LayoutKind::Scrolling
+    }
+
+    // new windows open in their own column right of the active one
+    fn add_window(&mut self, window: Rc<RefCell<CompWindow>>) {
+        let index = if self.columns.is_empty() {
+            0
+        } else {
+            self.active + 1
+        };
+        self.columns.insert(
+            index,
+            Column {
+                windows: vec![window],
+                width: CONFIG.default_column_width,
+            },
+        );
+        self.active = index;
+    }
+
+    fn remove_window(&mut self, window: &Window) {
+        let Some(index) = self.column_of(window) else {
+            return;
+        };
+        self.columns[index]
+            .windows
+            .retain(|w| &w.borrow().window != window);
+        if self.columns[index].windows.is_empty() {
+            self.columns.remove(index);
+            if index < self.active || self.active >= self.columns.len() {
+                self.active = self.active.saturating_sub(1);
+            }
+        }
+    }
+
+    fn swap_windows(&mut self, first: &Rc<RefCell<CompWindow>>, second: &Rc<RefCell<CompWindow>>) {
+        for column in &mut self.columns {
+            swap_slots(&mut column.windows, first, second);
+        }
+    }
+
+    fn arrange(&mut self, region: Rectangle<i32, Logical>, gaps: (i32, i32)) {
+        let region = shrink(region, gaps.0);
+        self.view_width = region.size.w;
+        let widths: Vec<i32> = self
+            .columns
+            .iter()
+            .map(|c| c.width_on(region.size.w))
+            .collect();
+
+        // scroll just enough to show the whole active column
+        if let Some(active_width) = widths.get(self.active) {
+            let active_x: i32 = widths[..self.active].iter().sum();
+            if active_x < self.view_offset {
+                self.view_offset = active_x;
+            } else if active_x + active_width > self.view_offset + region.size.w {
+                self.view_offset = active_x + active_width - region.size.w;
+            }
+        } else {
+            self.view_offset = 0;
+        }
+
+        let mut x = region.loc.x - self.view_offset;
+        for (column, width) in self.columns.iter().zip(widths) {
+            let column_rec = Rectangle {
+                loc: Point::from((x, region.loc.y)),
+                size: Size::from((width, region.size.h)),
+            };
+            let recs = split_rect(
+                column_rec,
+                column.windows.len(),
+                HorizontalOrVertical::Vertical,
+            );
+            for (window, rec) in column.windows.iter().zip(recs) {
+                window.borrow_mut().rec = shrink(rec, gaps.1);
+            }
+            x += width;
+        }
+    }
+
+    fn resize(&mut self, _update_interval: f32, increment: bool) {
+        if let Some(column) = self.columns.get_mut(self.active) {
+            column.width = if increment {
+                (column.width + 1).min(CONFIG.column_widths.len().saturating_sub(1))
+            } else {
+                column.width.saturating_sub(1)
+            };
+        }
+    }
+
+    fn cycle_width(&mut self) {
+        if let Some(column) = self.columns.get_mut(self.active) {
+            column.width = (column.width + 1) % CONFIG.column_widths.len().max(1);
+        }
+    }
+
+    // A window sharing its column is expelled into a new column next to it,
+    // a window alone in its column is consumed into the neighbouring column.
+    fn move_window(&mut self, window: &Window, forward: bool) {
+        let Some(index) = self.column_of(window) else {
+            return;
+        };
+        let position = self.columns[index]
+            .windows
+            .iter()
+            .position(|w| &w.borrow().window == window)
+            .unwrap();
+
+        if self.columns[index].windows.len() > 1 {
+            let moved = self.columns[index].windows.remove(position);
+            let width = self.columns[index].width;
+            let target = if forward { index + 1 } else { index };
+            self.columns.insert(
+                target,
+                Column {
+                    windows: vec![moved],
+                    width,
+                },
+            );
+            self.active = target;
+        } else {
+            let target = if forward {
+                index + 1
+            } else if index > 0 {
+                index - 1
+            } else {
+                return;
+            };
+            if target >= self.columns.len() {
+                return;
+            }
+            let moved = self.columns.remove(index).windows;
+            let target = if target > index { target - 1 } else { target };
+            self.columns[target].windows.extend(moved);
+            self.active = target;
+        }
+    }
+
+    // only scroll when the newly focused column is not fully shown
+    fn focus(&mut self, window: &Window) -> bool {
+        let Some(index) = self.column_of(window) else {
+            return false;
+        };
+        self.active = index;
+        let (start, end) = self.column_span(index);
+        start < self.view_offset || end > self.view_offset + self.view_width
+    }
+
+    // columns scrolled out of the viewport are neither drawn nor count for smart gaps
+    fn is_visible(&self, window: &Window) -> bool {
+        match self.column_of(window) {
+            // the viewport scrolls to the active column on the next arrange
+            Some(index) if index == self.active || self.view_width == 0 => true,
+            Some(index) => {
+                let (start, end) = self.column_span(index);
+                end > self.view_offset && start < self.view_offset + self.view_width
+            }
+            None => true,
+        }
+    }
+}
@@ -0,0 +1,13 @@
+use std::{fs, path::PathBuf};
+
+// Writes `value` to $XDG_RUNTIME_DIR/rustwm/<name> for status bars to watch,
+// there is no IPC to query the compositor yet.
+pub fn publish(name: &str, value: &str) {
+    let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR").map(|d| PathBuf::from(d).join("rustwm"))
+    else {
+        return;
+    };
+    if let Err(err) = fs::create_dir_all(&dir).and_then(|_| fs::write(dir.join(name), value)) {
+        eprintln!("{} Failed to publish {}", err, name);
+    }
+}
@@ -0,0 +1,143 @@
+use smithay::{
+    backend::renderer::{
+        element::{
+            solid::{SolidColorBuffer, SolidColorRenderElement},
+            surface::WaylandSurfaceRenderElement,
+            AsRenderElements,
+        },
+        ImportAll, Renderer, Texture,
+    },
+    desktop::{space::SpaceElement, Window},
+    utils::{Logical, Point, Rectangle, Scale},
+};
+
+use crate::state::CONFIG;
+
+// side length of the box shown for every window
+const ENTRY_SIZE: i32 = 160;
+const ENTRY_GAP: i32 = 8;
+// part of the box around the thumbnail, it shows whether the entry is selected
+const THUMBNAIL_PADDING: i32 = 6;
+
+// Alt-tab style window switcher: a row of window thumbnails in most recently used
+// order, framed by boxes with the selected one highlighted. The selection is focused once the
+// modifiers are released.
+pub struct Switcher {
+    windows: Vec<Window>,
+    selected: usize,
+    boxes: Vec<SolidColorBuffer>,
+}
+
+impl Switcher {
+    // Starts on the window after (or before) the focused one
+    pub fn new(windows: Vec<Window>, forward: bool) -> Self {
+        let boxes = windows
+            .iter()
+            .map(|_| SolidColorBuffer::new((ENTRY_SIZE, ENTRY_SIZE), CONFIG.inactive_tab_color))
+            .collect();
+        let mut switcher = Switcher {
+            selected: 0,
+            windows,
+            boxes,
+        };
+        switcher.cycle(forward);
+        switcher
+    }
+
+    pub fn cycle(&mut self, forward: bool) {
+        let len = self.windows.len();
+        if len == 0 {
+            return;
+        }
+        self.selected = if forward {
+            (self.selected + 1) % len
+        } else {
+            (self.selected + len - 1) % len
+        };
+        for (index, buffer) in self.boxes.iter_mut().enumerate() {
+            let color = if index == self.selected {
+                CONFIG.active_tab_color
+            } else {
+                CONFIG.inactive_tab_color
+            };
+            buffer.update((ENTRY_SIZE, ENTRY_SIZE), color);
+        }
+    }
+
+    pub fn selected(&self) -> Option<&Window> {
+        self.windows.get(self.selected)
+    }
+
+    // The boxes centered on an output of the given geometry, in output local coordinates,
+    // each with a thumbnail of its window on top
+    pub fn render_elements<R, C>(
+        &self,
+        renderer: &mut R,
+        output_geo: Rectangle<i32, Logical>,
+        scale: f64,
+    ) -> Vec<C>
+    where
+        R: Renderer + ImportAll,
+        <R as Renderer>::TextureId: Texture + 'static,
+        C: From<WaylandSurfaceRenderElement<R>> + From<SolidColorRenderElement>,
+    {
+        let count = self.boxes.len() as i32;
+        let width = count * ENTRY_SIZE + (count - 1).max(0) * ENTRY_GAP;
+        let x = (output_geo.size.w - width) / 2;
+        let y = (output_geo.size.h - ENTRY_SIZE) / 2;
+        let mut thumbnails = Vec::new();
+        let mut boxes = Vec::new();
+        for (index, (buffer, window)) in self.boxes.iter().zip(&self.windows).enumerate() {
+            let loc = Point::<i32, Logical>::from((x + index as i32 * (ENTRY_SIZE + ENTRY_GAP), y));
+            thumbnails.extend(thumbnail(renderer, window, loc, scale));
+            boxes.push(
+                SolidColorRenderElement::from_buffer(
+                    buffer,
+                    loc.to_physical_precise_round(scale),
+                    Scale::from(scale),
+                )
+                .into(),
+            );
+        }
+        // the first elements are drawn on top
+        thumbnails.extend(boxes);
+        thumbnails
+    }
+}
+
+// `window` scaled down to fit the box at `loc`, keeping its aspect ratio
+fn thumbnail<R, C>(
+    renderer: &mut R,
+    window: &Window,
+    loc: Point<i32, Logical>,
+    scale: f64,
+) -> Vec<C>
+where
+    R: Renderer + ImportAll,
+    <R as Renderer>::TextureId: Texture + 'static,
+    C: From<WaylandSurfaceRenderElement<R>>,
+{
+    let geometry = window.geometry();
+    if geometry.size.w <= 0 || geometry.size.h <= 0 {
+        return Vec::new();
+    }
+    let area = ENTRY_SIZE - 2 * THUMBNAIL_PADDING;
+    let factor = f64::min(
+        area as f64 / geometry.size.w as f64,
+        area as f64 / geometry.size.h as f64,
+    );
+    let (w, h) = (
+        geometry.size.w as f64 * factor,
+        geometry.size.h as f64 * factor,
+    );
+    let centered = loc.to_f64()
+        + Point::from((
+            THUMBNAIL_PADDING as f64 + (area as f64 - w) / 2.0,
+            THUMBNAIL_PADDING as f64 + (area as f64 - h) / 2.0,
+        ));
+    // the surfaces start at the origin of the window geometry, not at the window's corner
+    let location = (centered.to_physical(scale)
+        - geometry.loc.to_f64().to_physical(scale * factor))
+    .to_i32_round();
+    window.render_elements(renderer, location, Scale::from(scale * factor))
+}
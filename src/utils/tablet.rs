@@ -0,0 +1,91 @@
+use std::sync::{Arc, Mutex};
+
+use smithay::{
+    backend::renderer::{
+        element::{
+            solid::{SolidColorBuffer, SolidColorRenderElement},
+            surface::{render_elements_from_surface_tree, WaylandSurfaceRenderElement},
+        },
+        ImportAll, Renderer, Texture,
+    },
+    input::pointer::{CursorImageAttributes, CursorImageStatus},
+    utils::{IsAlive, Logical, Point, Rectangle, Scale},
+    wayland::compositor::with_states,
+};
+
+use crate::state::CONFIG;
+
+// side length of the dot drawn while the client under the tool sets no cursor
+const DOT_SIZE: i32 = 6;
+
+// Cursor of the tablet tool in proximity. The compositor has to draw it, only the
+// pointer gets the cursor of the backend.
+pub struct TabletCursor {
+    pub location: Option<Point<f64, Logical>>,
+    // set by the client under the tool through the tablet protocol
+    pub image: Arc<Mutex<CursorImageStatus>>,
+    dot: SolidColorBuffer,
+}
+
+impl Default for TabletCursor {
+    fn default() -> Self {
+        TabletCursor {
+            location: None,
+            image: Arc::new(Mutex::new(CursorImageStatus::Default)),
+            dot: SolidColorBuffer::new((DOT_SIZE, DOT_SIZE), CONFIG.active_tab_color),
+        }
+    }
+}
+
+impl TabletCursor {
+    // The cursor in the coordinates of the output with the given geometry
+    pub fn render_elements<
+        R: Renderer + ImportAll,
+        C: From<WaylandSurfaceRenderElement<R>> + From<SolidColorRenderElement>,
+    >(
+        &self,
+        renderer: &mut R,
+        output_geo: Rectangle<i32, Logical>,
+        scale: f64,
+    ) -> Vec<C>
+    where
+        <R as Renderer>::TextureId: Texture + 'static,
+    {
+        let Some(location) = self.location.filter(|l| output_geo.to_f64().contains(*l)) else {
+            return Vec::new();
+        };
+        let location = location.to_i32_round() - output_geo.loc;
+        let mut image = self.image.lock().unwrap();
+        // the surface goes away with its client, which can't reset the image anymore
+        if matches!(&*image, CursorImageStatus::Surface(surface) if !surface.alive()) {
+            *image = CursorImageStatus::Default;
+        }
+        match &*image {
+            CursorImageStatus::Hidden => Vec::new(),
+            CursorImageStatus::Surface(surface) => {
+                let hotspot = with_states(surface, |states| {
+                    states
+                        .data_map
+                        .get::<Mutex<CursorImageAttributes>>()
+                        .map(|attributes| attributes.lock().unwrap().hotspot)
+                })
+                .unwrap_or_default();
+                render_elements_from_surface_tree(
+                    renderer,
+                    surface,
+                    (location - hotspot).to_physical_precise_round(scale),
+                    Scale::from(scale),
+                )
+            }
+            _ => {
+                let loc = location - Point::from((DOT_SIZE / 2, DOT_SIZE / 2));
+                vec![SolidColorRenderElement::from_buffer(
+                    &self.dot,
+                    loc.to_physical_precise_round(scale),
+                    Scale::from(scale),
+                )
+                .into()]
+            }
+        }
+    }
+}
@@ -1,31 +1,87 @@
-use std::{cell::RefCell, rc::Rc};
-
 use crate::state::CONFIG;
 use smithay::{
     desktop::layer_map_for_output,
-    utils::{Logical, Physical, Point, Rectangle, Size},
+    utils::{Logical, Point, Rectangle, Size},
 };
 use super::{
     binarytree::{BinaryTree, HorizontalOrVertical},
-    workspace::{CompWindow, Workspace},
+    workspace::Workspace,
 };
 
-pub fn bsp_update_layout(workspace: &mut Workspace) {
-    let gaps = CONFIG.gaps;
+// Recomputes the geometry of every window on the workspace with its current layout
+// and sends the new sizes to the clients.
+pub fn update_layout(workspace: &mut Workspace) {
+    let Some(output) = workspace.outputs().next().cloned() else {
+        return;
+    };
+    let mut region = layer_map_for_output(&output).non_exclusive_zone();
+    // the layer map works in output local coordinates
+    region.loc += output.current_location();
+    let gaps = workspace_gaps(workspace, &output.name());
+
+    workspace.layout.arrange(region, gaps);
+
+    // fullscreen windows cover the layer surfaces as well
+    if let Some(output_geo) = workspace.output_geometry(&output) {
+        for window in &workspace.windows {
+            let mut window = window.borrow_mut();
+            if window.fullscreen {
+                window.rec = output_geo;
+            }
+        }
+    }
+
+    for compwindow in workspace.comp_windows() {
+        let xdg_toplevel = compwindow.window.toplevel();
+        xdg_toplevel.with_pending_state(|state| {
+            state.size = Some(compwindow.rec.size);
+        });
+        xdg_toplevel.send_configure();
+    }
+    workspace.send_output_state();
+}
+
+// Gaps of the workspace when shown on the output called `output_name`.
+// The workspace config wins over the output config, runtime changes are added on top.
+pub fn configured_gaps(workspace: &Workspace, output_name: &str) -> (i32, i32) {
+    let (outer, inner) = workspace
+        .gaps
+        .or_else(|| CONFIG.output_gaps.get(output_name).copied())
+        .unwrap_or(CONFIG.gaps);
+    (
+        (outer + workspace.gaps_delta.0).max(0),
+        (inner + workspace.gaps_delta.1).max(0),
+    )
+}
 
-    let output = layer_map_for_output(workspace.outputs().next().unwrap()).non_exclusive_zone();
-    let output_full = workspace
-        .outputs()
-        .next()
-        .unwrap()
-        .current_mode()
-        .unwrap()
-        .size;
+// Smart gaps leave a lone tiled window without gaps and border
+pub fn smart_gaps_apply(workspace: &Workspace) -> bool {
+    CONFIG.smart_gaps
+        && workspace
+            .comp_windows()
+            .filter(|w| w.is_tiled() && workspace.is_visible(w))
+            .count()
+            == 1
+}
 
-    match &mut workspace.layout_tree {
+// Like `configured_gaps`, but without any gaps if smart gaps apply
+pub fn workspace_gaps(workspace: &Workspace, output_name: &str) -> (i32, i32) {
+    if smart_gaps_apply(workspace) {
+        return (0, 0);
+    }
+    configured_gaps(workspace, output_name)
+}
+
+pub fn bsp_arrange(tree: &mut BinaryTree, output: Rectangle<i32, Logical>, gaps: (i32, i32)) {
+    let output_end = Size::from((
+        output.loc.x + output.size.w - gaps.0,
+        output.loc.y + output.size.h - gaps.0,
+    ));
+
+    match tree {
         BinaryTree::Empty => {}
-        BinaryTree::Window(w) => {
-            w.borrow_mut().rec = Rectangle {
+        BinaryTree::Window(_) | BinaryTree::Container { .. } => {
+            tree.set_geometry(Rectangle {
                 loc: Point::from((
                     gaps.0 + gaps.1 + output.loc.x,
                     gaps.0 + gaps.1 + output.loc.y,
@@ -34,7 +90,7 @@ pub fn bsp_update_layout(workspace: &mut Workspace) {
                     output.size.w - ((gaps.0 + gaps.1) * 2),
                     output.size.h - ((gaps.0 + gaps.1) * 2),
                 )),
-            };
+            });
         }
         BinaryTree::Split {
             left,
@@ -43,42 +99,75 @@ pub fn bsp_update_layout(workspace: &mut Workspace) {
             ratio,
             counter_ratio: _,
         } => {
-            if let BinaryTree::Window(w) = left.as_mut() {
-                generate_layout(
-                    right.as_mut(),
-                    w,
-                    Rectangle {
-                        loc: Point::from((gaps.0 + output.loc.x, gaps.0 + output.loc.y)),
-                        size: Size::from((
-                            output.size.w - (gaps.0 * 2),
-                            output.size.h - (gaps.0 * 2),
-                        )),
-                    },
-                    *split,
-                    *ratio,
-                    Size::from((output_full.w - gaps.0, output_full.h - gaps.0)),
-                    gaps,
-                )
-            }
+            generate_layout(
+                right.as_mut(),
+                left.as_mut(),
+                Rectangle {
+                    loc: Point::from((gaps.0 + output.loc.x, gaps.0 + output.loc.y)),
+                    size: Size::from((
+                        output.size.w - (gaps.0 * 2),
+                        output.size.h - (gaps.0 * 2),
+                    )),
+                },
+                *split,
+                *ratio,
+                output_end,
+                gaps,
+            )
         }
     }
-    for compwindow in workspace.comp_windows() {
-        let xdg_toplevel = compwindow.window.toplevel();
-        xdg_toplevel.with_pending_state(|state| {
-            state.size = Some(compwindow.rec.size);
-        });
-        xdg_toplevel.send_configure();
+}
+
+// Shrinks `rec` by `gap` on every side.
+pub fn shrink(rec: Rectangle<i32, Logical>, gap: i32) -> Rectangle<i32, Logical> {
+    Rectangle {
+        loc: Point::from((rec.loc.x + gap, rec.loc.y + gap)),
+        size: Size::from((rec.size.w - gap * 2, rec.size.h - gap * 2)),
     }
 }
 
+// Cuts `rec` into `count` slices of (almost) equal size.
+// `Horizontal` places the slices side by side, `Vertical` on top of each other,
+// matching the meaning of the split direction in the `BinaryTree`.
+pub fn split_rect(
+    rec: Rectangle<i32, Logical>,
+    count: usize,
+    split: HorizontalOrVertical,
+) -> Vec<Rectangle<i32, Logical>> {
+    if count == 0 {
+        return Vec::new();
+    }
+    let count = count as i32;
+    let total = match split {
+        HorizontalOrVertical::Horizontal => rec.size.w,
+        HorizontalOrVertical::Vertical => rec.size.h,
+    };
+    (0..count)
+        .map(|i| {
+            // distribute the remainder so the slices cover `rec` exactly
+            let start = total * i / count;
+            let end = total * (i + 1) / count;
+            match split {
+                HorizontalOrVertical::Horizontal => Rectangle {
+                    loc: Point::from((rec.loc.x + start, rec.loc.y)),
+                    size: Size::from((end - start, rec.size.h)),
+                },
+                HorizontalOrVertical::Vertical => Rectangle {
+                    loc: Point::from((rec.loc.x, rec.loc.y + start)),
+                    size: Size::from((rec.size.w, end - start)),
+                },
+            }
+        })
+        .collect()
+}
 
 pub fn generate_layout(
     tree: &mut BinaryTree,
-    lastwin: &Rc<RefCell<CompWindow>>,
+    lastleaf: &mut BinaryTree,
     lastgeo: Rectangle<i32, Logical>,
     split: HorizontalOrVertical,
     ratio: f32,
-    output: Size<i32, Physical>,
+    output: Size<i32, Logical>,
     gaps: (i32, i32),
 ) {
     let size: Size<i32, Logical> = match split {
@@ -100,7 +189,7 @@ pub fn generate_layout(
         loc: Point::from((loc.x + gaps.1, loc.y + gaps.1)),
     };
 
-    lastwin.borrow_mut().rec = recgapped;
+    lastleaf.set_geometry(recgapped);
     let counter_ratio = 1.0f32 - ratio;
     let size = match split {
         HorizontalOrVertical::Horizontal => Size::from((
@@ -125,7 +214,7 @@ pub fn generate_layout(
     };
     match tree {
         BinaryTree::Empty => {}
-        BinaryTree::Window(w) => w.borrow_mut().rec = recgapped,
+        BinaryTree::Window(_) | BinaryTree::Container { .. } => tree.set_geometry(recgapped),
         BinaryTree::Split {
             split,
             ratio: _,
@@ -133,10 +222,8 @@ pub fn generate_layout(
             left,
             right,
         } => {
-            if let BinaryTree::Window(w) = left.as_mut() {
-                w.borrow_mut().rec = rec;
-                generate_layout(right.as_mut(), w, rec, *split, *counter_ratio, output, gaps)
-            }
+            // `left` is always a leaf, it gets its final geometry from the recursive call
+            generate_layout(right.as_mut(), left.as_mut(), rec, *split, *counter_ratio, output, gaps)
         }
     }
 }
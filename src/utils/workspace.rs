@@ -1,26 +1,90 @@
 use std::{
     cell::{Ref, RefCell},
+    collections::HashMap,
     rc::Rc,
+    sync::Mutex,
 };
 
 use smithay::{
     backend::renderer::{
-        element::{surface::WaylandSurfaceRenderElement, AsRenderElements},
+        element::{
+            solid::SolidColorRenderElement, surface::WaylandSurfaceRenderElement,
+            AsRenderElements,
+        },
         ImportAll, Renderer, Texture,
     },
     desktop::{space::SpaceElement, Window},
     output::Output,
-    utils::{Logical, Point, Rectangle, Scale, Transform},
+    reexports::wayland_protocols::xdg::shell::server::xdg_toplevel::State as ToplevelState,
+    utils::{Logical, Point, Rectangle, Scale, Size, Transform},
+    wayland::{
+        compositor::{with_states, with_surface_tree_downward, TraversalAction},
+        fractional_scale::with_fractional_scale,
+        shell::xdg::XdgToplevelSurfaceRoleAttributes,
+    },
 };
 
-use super::{binarytree::BinaryTree, tiling::bsp_update_layout};
+use crate::{config::LayoutKind, state::CONFIG};
+
+use super::{
+    layout::{new_layout, swap_slots, Layout},
+    render::{translucent_window_elements, Border, OpacityRenderElement},
+    saved_layout::{self, SavedTree},
+    status,
+    tiling::{smart_gaps_apply, update_layout},
+};
+
+// What `Workspaces::toggle_named_scratchpad` did
+pub enum ScratchpadToggle {
+    // the scratchpad has no window yet, its command needs to be spawned
+    Spawn,
+    // the command was spawned already and its window has not been mapped yet
+    Pending,
+    Hidden,
+    Shown(Window),
+}
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct CompWindow {
     pub window: Window,
     pub rec: Rectangle<i32, Logical>,
+    // floating and fullscreen windows are not part of the layout
+    pub floating: bool,
+    pub fullscreen: bool,
+    // scratchpad windows go back to the scratchpad instead of being toggled off
+    pub scratchpad: bool,
+    // labels set with `Action::Mark`, every mark is on at most one window
+    pub marks: Vec<String>,
+    // sticky windows move along to whatever workspace is shown on their output
+    pub sticky: bool,
+    // keyboard layout active while the window was focused, see `KeyboardConfig::per_window_layout`
+    pub keyboard_layout: usize,
+    // set by window rules
+    pub no_border: bool,
+    pub opacity: f32,
+    border: Border,
 }
 impl CompWindow {
+    pub fn new(window: Window) -> Self {
+        CompWindow {
+            rec: window.geometry(),
+            window,
+            floating: false,
+            fullscreen: false,
+            scratchpad: false,
+            marks: Vec::new(),
+            sticky: false,
+            keyboard_layout: 0,
+            no_border: false,
+            opacity: 1.0,
+            border: Border::default(),
+        }
+    }
+
+    pub fn is_tiled(&self) -> bool {
+        !self.floating && !self.fullscreen
+    }
+
     fn bbox(&self) -> Rectangle<i32, Logical> {
         let mut bbox = self.window.bbox();
         bbox.loc += self.rec.loc - self.window.geometry().loc;
@@ -30,21 +94,69 @@ impl CompWindow {
     fn render_location(&self) -> Point<i32, Logical> {
         self.rec.loc - self.window.geometry().loc
     }
+
+    // Tells the client which output the window is on and at which scale to render it
+    fn enter_output(&self, output: &Output) {
+        self.window
+            .output_enter(output, Rectangle::from_loc_and_size((0, 0), self.rec.size));
+        let scale = output.current_scale().fractional_scale();
+        with_surface_tree_downward(
+            self.window.toplevel().wl_surface(),
+            (),
+            |_, _, _| TraversalAction::DoChildren(()),
+            |_, states, _| {
+                with_fractional_scale(states, |fractional| {
+                    fractional.set_preferred_scale(scale);
+                });
+            },
+            |_, _, _| true,
+        );
+    }
+}
+// app_id and title of a toplevel, both are only known after its initial commit
+pub fn window_identity(window: &Window) -> (Option<String>, Option<String>) {
+    with_states(window.toplevel().wl_surface(), |states| {
+        let attributes = states
+            .data_map
+            .get::<Mutex<XdgToplevelSurfaceRoleAttributes>>()
+            .unwrap()
+            .lock()
+            .unwrap();
+        (attributes.app_id.clone(), attributes.title.clone())
+    })
 }
+
 pub struct Workspace {
+    pub name: String,
+    // persistent workspaces are never destroyed, even when empty
+    pub persistent: bool,
     pub windows: Vec<Rc<RefCell<CompWindow>>>,
-    outputs: Vec<Output>,
-    pub layout_tree: BinaryTree,
+    // the output the workspace is bound to, it is only shown there
+    output: Option<Output>,
+    pub layout: Box<dyn Layout>,
+    // overrides the configured gaps, see `tiling::workspace_gaps`
+    pub gaps: Option<(i32, i32)>,
+    // runtime change of the (outer, inner) gaps through `Action::AdjustGaps`
+    pub gaps_delta: (i32, i32),
 }
 
 impl Workspace {
-    pub fn new() -> Self {
+    pub fn new(name: String) -> Self {
         Workspace {
+            gaps: CONFIG.workspace_gaps.get(&name).copied(),
+            gaps_delta: (0, 0),
+            name,
+            persistent: false,
             windows: Vec::new(),
-            outputs: Vec::new(),
-            layout_tree: BinaryTree::new(),
+            output: None,
+            layout: new_layout(CONFIG.default_layout),
         }
     }
+
+    // Placeholders of a loaded layout keep a workspace alive as well
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty() && !self.layout.has_placeholders()
+    }
     
     pub fn windows(&self) -> impl Iterator<Item = Ref<'_, Window>> {
         self.windows
@@ -60,10 +172,14 @@ impl Workspace {
         // add window to vec and remap if exists
         self.windows
             .retain(|w| w.borrow().window != window.borrow().window);
-        self.windows.push(window.clone());
-        self.layout_tree
-            .insert(window, self.layout_tree.next_split(), 0.5);
-        bsp_update_layout(self);
+        if window.borrow().is_tiled() {
+            self.windows.push(window.clone());
+            self.layout.add_window(window);
+        } else {
+            // keep floating windows above the tiled ones
+            self.windows.insert(0, window);
+        }
+        update_layout(self);
     }
 
     pub fn remove_window(&mut self, window: &Window) -> Option<Rc<RefCell<CompWindow>>> {   
@@ -76,52 +192,181 @@ impl Workspace {
                 true
             }
         });
-        self.layout_tree.remove(window);
-        // to fix windows horizontal-vertical division layout
-        self.layout_tree.update_after_removal(super::binarytree::HorizontalOrVertical::Horizontal);
-        bsp_update_layout(self);
+        self.layout.remove_window(window);
+        if let (Some(output), Some(removed)) = (&self.output, &removed) {
+            removed.borrow().window.output_leave(output);
+        }
+        update_layout(self);
         removed
     }
 
-    pub fn render_elements<R: Renderer + ImportAll, C: From<WaylandSurfaceRenderElement<R>>>(
+    // Replaces the layout of the workspace, keeping the windows in the order they were added
+    pub fn set_layout(&mut self, kind: LayoutKind) {
+        self.layout = new_layout(kind);
+        for window in self.windows.iter().filter(|w| w.borrow().is_tiled()) {
+            self.layout.add_window(window.clone());
+        }
+        update_layout(self);
+    }
+
+    // Pre-builds the saved tree, new windows matching its placeholders are swallowed into it
+    pub fn load_layout(&mut self, tree: SavedTree) {
+        // only the tiling tree can be persisted
+        if self.layout.kind() != LayoutKind::Bsp {
+            self.set_layout(LayoutKind::Bsp);
+        }
+        self.layout.load(tree);
+        update_layout(self);
+    }
+
+    // Whether the window is shown, the layout only decides for the windows it tiles
+    pub fn is_visible(&self, window: &CompWindow) -> bool {
+        !window.is_tiled() || self.layout.is_visible(&window.window)
+    }
+
+    // Returns the window after (or before) `current` in the order they were added
+    pub fn next_window(&self, current: Option<&Window>, forward: bool) -> Option<Window> {
+        let len = self.windows.len();
+        if len == 0 {
+            return None;
+        }
+        let index = current.and_then(|c| self.windows.iter().position(|w| &w.borrow().window == c));
+        let next = match index {
+            Some(i) if forward => (i + 1) % len,
+            Some(i) => (i + len - 1) % len,
+            None => 0,
+        };
+        Some(self.windows[next].borrow().window.clone())
+    }
+
+    // `offset` moves the workspace relative to its output, while it is swiped aside
+    pub fn render_elements<
+        R: Renderer + ImportAll,
+        C: From<WaylandSurfaceRenderElement<R>>
+            + From<SolidColorRenderElement>
+            + From<OpacityRenderElement<R>>,
+    >(
         &self,
         renderer: &mut R,
+        offset: Point<i32, Logical>,
     ) -> Vec<C>
     where
         <R as Renderer>::TextureId: Texture + 'static,
     {
-        let mut render_elements: Vec<C> = Vec::new();
-        for element in &self.windows {
-            render_elements.append(&mut element.borrow().window.render_elements(
-                renderer,
-                element.borrow().render_location().to_physical(1),
-                Scale::from(1.0),
-            ));
+        // the part of the workspace that ends up on the output
+        let output_geo = self
+            .output
+            .as_ref()
+            .and_then(|o| self.output_geometry(o))
+            .map(|geo| Rectangle::from_loc_and_size(geo.loc - offset, geo.size));
+        // elements are placed relative to the output they are rendered on
+        let origin = output_geo.map(|geo| geo.loc).unwrap_or_default();
+        let scale = self
+            .output
+            .as_ref()
+            .map(|o| o.current_scale().fractional_scale())
+            .unwrap_or(1.0);
+        let lone_tile = smart_gaps_apply(self);
+        let mut render_elements: Vec<C> = self
+            .layout
+            .decorations()
+            .into_iter()
+            .map(|(buffer, loc)| {
+                SolidColorRenderElement::from_buffer(
+                    &buffer,
+                    (loc - origin).to_physical_precise_round(scale),
+                    Scale::from(scale),
+                )
+                .into()
+            })
+            .collect();
+        for element in self
+            .windows
+            .iter()
+            .filter(|w| self.is_visible(&w.borrow()))
+            // layouts like the scrolling one place windows outside of the output
+            .filter(|w| output_geo.is_none_or(|geo| geo.overlaps(w.borrow().bbox())))
+        {
+            let window = &mut *element.borrow_mut();
+            let location = (window.render_location() - origin).to_physical_precise_round(scale);
+            if window.opacity < 1.0 {
+                render_elements.extend(translucent_window_elements(
+                    renderer,
+                    &window.window,
+                    location,
+                    scale,
+                    window.opacity,
+                ));
+            } else {
+                render_elements.append(&mut window.window.render_elements(
+                    renderer,
+                    location,
+                    Scale::from(scale),
+                ));
+            }
+            let borderless =
+                window.no_border || window.fullscreen || (lone_tile && window.is_tiled());
+            if CONFIG.border_width > 0 && !borderless {
+                let activated = window
+                    .window
+                    .toplevel()
+                    .current_state()
+                    .states
+                    .contains(ToplevelState::Activated);
+                let color = if activated {
+                    CONFIG.active_border_color
+                } else {
+                    CONFIG.inactive_border_color
+                };
+                let rec = window.rec;
+                for (buffer, loc) in window.border.update(rec, CONFIG.border_width, color) {
+                    render_elements.push(
+                        SolidColorRenderElement::from_buffer(
+                            buffer,
+                            (*loc - origin).to_physical_precise_round(scale),
+                            Scale::from(scale),
+                        )
+                        .into(),
+                    );
+                }
+            }
         }
         render_elements
     }
 
     pub fn outputs(&self) -> impl Iterator<Item = &Output> {
-        self.outputs.iter()
+        self.output.iter()
     }
 
-    pub fn add_output(&mut self, output: Output) {
-        self.outputs.push(output);
+    // Binds the workspace to another output and retiles it there
+    pub fn set_output(&mut self, output: Option<Output>) {
+        if let Some(old) = &self.output {
+            for window in self.windows() {
+                window.output_leave(old);
+            }
+        }
+        self.output = output;
+        update_layout(self);
     }
 
-    pub fn remove_output(&mut self, output: &Output) {
-        self.outputs.retain(|o| o != output);
+    // Sends the output and its scale to every window, after the layout or the output changed
+    pub fn send_output_state(&self) {
+        if let Some(output) = &self.output {
+            for window in self.comp_windows() {
+                window.enter_output(output);
+            }
+        }
     }
 
     pub fn output_geometry(&self, o: &Output) -> Option<Rectangle<i32, Logical>> {
-        if !self.outputs.contains(o) {
+        if self.output.as_ref() != Some(o) {
             return None;
         }
 
         let transform: Transform = o.current_transform();
         o.current_mode().map(|mode| {
             Rectangle::from_loc_and_size(
-                (0, 0),
+                o.current_location(),
                 transform
                     .transform_size(mode.size)
                     .to_f64()
@@ -138,6 +383,7 @@ impl Workspace {
         let point = point.into();
         self.windows
             .iter()
+            .filter(|e| self.is_visible(&e.borrow()))
             .filter(|e| e.borrow().bbox().to_f64().contains(point))
             .find_map(|e| {
                 // we need to offset the point to the location where the surface is actually drawn
@@ -158,63 +404,641 @@ impl Workspace {
     }
 }
 
-impl Default for Workspace {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 pub struct Workspaces {
+    // kept in navigation order, see `order_key`
     workspaces: Vec<Workspace>,
-    pub current: u8,
+    // every connected output with the name of the workspace it shows
+    shown: Vec<(Output, String)>,
+    // the workspace on the focused output
+    pub current: String,
+    // the workspace `activate` switched away from last, for back-and-forth switching
+    pub previous: Option<String>,
+    // hidden scratchpad windows, the oldest first
+    scratchpad: Vec<Rc<RefCell<CompWindow>>>,
+    // windows captured by the named scratchpads of the config
+    named_scratchpads: HashMap<String, Window>,
+    // named scratchpads waiting for the window of their command
+    spawned_scratchpads: Vec<String>,
+}
+
+// Numbered workspaces come first in numeric order, named ones follow alphabetically
+fn order_key(name: &str) -> (u32, String) {
+    (name.parse().unwrap_or(u32::MAX), name.to_string())
 }
 
 impl Workspaces {
-    pub fn new(workspaceamount: u8) -> Self {
-        Workspaces {
-            workspaces: (0..workspaceamount).map(|_| Workspace::new()).collect(),
-            current: 0,
-        }
+    // Creates the persistent workspaces "1" to `persistent`,
+    // every other workspace is created when it is first used
+    pub fn new(persistent: u8) -> Self {
+        let mut workspaces = Workspaces {
+            workspaces: (1..=persistent)
+                .map(|id| Workspace {
+                    persistent: true,
+                    ..Workspace::new(id.to_string())
+                })
+                .collect(),
+            shown: Vec::new(),
+            current: String::from("1"),
+            previous: None,
+            scratchpad: Vec::new(),
+            named_scratchpads: HashMap::new(),
+            spawned_scratchpads: Vec::new(),
+        };
+        workspaces.get_or_create("1");
+        workspaces
     }
 
     pub fn outputs(&self) -> impl Iterator<Item = &Output> {
-        self.workspaces.iter().flat_map(|w| w.outputs())
+        self.shown.iter().map(|(o, _)| o)
     }
 
-    pub fn iter(&mut self) -> impl Iterator<Item = &mut Workspace> {
-        self.workspaces.iter_mut()
+    pub fn output_geometry(&self, output: &Output) -> Option<Rectangle<i32, Logical>> {
+        self.shown_on(output)
+            .and_then(|w| w.output_geometry(output))
+    }
+
+    pub fn output_under(&self, point: Point<f64, Logical>) -> Option<Output> {
+        self.outputs()
+            .find(|o| {
+                self.output_geometry(o)
+                    .is_some_and(|geo| geo.to_f64().contains(point))
+            })
+            .cloned()
+    }
+
+    pub fn shown_on(&self, output: &Output) -> Option<&Workspace> {
+        let (_, name) = self.shown.iter().find(|(o, _)| o == output)?;
+        self.get(name)
+    }
+
+    // The workspace shown below `point`, or the current one if there is no output
+    pub fn under(&self, point: Point<f64, Logical>) -> &Workspace {
+        self.output_under(point)
+            .and_then(|o| self.shown_on(&o))
+            .unwrap_or_else(|| self.current())
+    }
+
+    fn is_shown(&self, name: &str) -> bool {
+        self.shown.iter().any(|(_, shown)| shown == name)
+    }
+
+    // Whether the window is on a workspace shown on some output
+    pub fn is_window_shown(&self, window: &Window) -> bool {
+        self.workspaces
+            .iter()
+            .any(|w| w.contains_window(window) && self.is_shown(&w.name))
+    }
+
+    // The output for a workspace without one: the output preferred in the config
+    // if it is connected, otherwise the focused one
+    fn output_for(&self, name: &str) -> Option<Output> {
+        let preferred = CONFIG.workspace_outputs.get(name);
+        self.outputs()
+            .find(|o| Some(&o.name()) == preferred)
+            .or_else(|| self.get(&self.current).and_then(|w| w.outputs().next()))
+            .or_else(|| self.outputs().next())
+            .cloned()
+    }
+
+    // Shows a workspace on a newly connected output: a hidden workspace preferring it,
+    // or one without an output, or a new one
+    pub fn add_output(&mut self, output: Output) {
+        let returning: Vec<String> = self
+            .workspaces
+            .iter()
+            .filter(|w| !self.is_shown(&w.name))
+            .filter(|w| CONFIG.workspace_outputs.get(&w.name) == Some(&output.name()))
+            .map(|w| w.name.clone())
+            .collect();
+        for name in &returning {
+            self.get_mut(name).unwrap().set_output(Some(output.clone()));
+        }
+
+        let name = returning
+            .first()
+            .cloned()
+            .or_else(|| {
+                self.workspaces
+                    .iter()
+                    .find(|w| w.outputs().next().is_none())
+                    .map(|w| w.name.clone())
+            })
+            .unwrap_or_else(|| {
+                (1..)
+                    .map(|id: u32| id.to_string())
+                    .find(|name| self.get(name).is_none())
+                    .unwrap()
+            });
+        self.show_on(&output, &name);
+        self.get_or_create(&name).set_output(Some(output));
+        if self.shown.len() == 1 {
+            self.current = name;
+        }
+
+        // workspaces created before there was any output
+        let unbound: Vec<String> = self
+            .workspaces
+            .iter()
+            .filter(|w| w.outputs().next().is_none())
+            .map(|w| w.name.clone())
+            .collect();
+        for name in unbound {
+            let output = self.output_for(&name);
+            self.get_mut(&name).unwrap().set_output(output);
+        }
+    }
+
+    // Moves the workspaces of a disconnected output to the first remaining one
+    pub fn remove_output(&mut self, output: &Output) {
+        let removed = self
+            .shown
+            .iter()
+            .position(|(o, _)| o == output)
+            .map(|index| self.shown.remove(index).1);
+        let fallback = self.outputs().next().cloned();
+        // floating windows keep their place relative to the output
+        if let Some(fallback) = &fallback {
+            let offset = fallback.current_location() - output.current_location();
+            self.translate_floating(output, offset);
+        }
+        for workspace in self
+            .workspaces
+            .iter_mut()
+            .filter(|w| w.outputs().any(|o| o == output))
+        {
+            workspace.set_output(fallback.clone());
+        }
+        // the workspace of the removed output is hidden now, its sticky windows
+        // go to the one shown on the fallback output
+        let shown = fallback
+            .as_ref()
+            .and_then(|o| self.shown_on(o))
+            .map(|w| w.name.clone());
+        if let (Some(removed), Some(shown)) = (removed, shown) {
+            self.carry_sticky(&removed, &shown);
+        }
+        if !self.is_shown(&self.current) {
+            if let Some((_, name)) = self.shown.first() {
+                self.current = name.clone();
+            }
+        }
+        self.cleanup();
+    }
+
+    // Moves the floating windows of every workspace bound to `output` along with it,
+    // tiled windows are placed again by their layout
+    pub fn translate_floating(&mut self, output: &Output, offset: Point<i32, Logical>) {
+        for workspace in self
+            .workspaces
+            .iter()
+            .filter(|w| w.outputs().any(|o| o == output))
+        {
+            for window in &workspace.windows {
+                let mut window = window.borrow_mut();
+                if window.floating {
+                    window.rec.loc += offset;
+                }
+            }
+        }
+    }
+
+    // Recomputes every workspace bound to `output` after its mode, scale or position changed
+    pub fn retile(&mut self, output: &Output) {
+        for workspace in self
+            .workspaces
+            .iter_mut()
+            .filter(|w| w.outputs().any(|o| o == output))
+        {
+            update_layout(workspace);
+        }
+    }
+
+    // Makes the workspace shown on `output` the current one,
+    // used when the pointer or the keyboard focus moves to another output
+    pub fn focus_output(&mut self, output: &Output) {
+        let Some(name) = self.shown_on(output).map(|w| w.name.clone()) else {
+            return;
+        };
+        // only explicit switches count for back-and-forth, not moving between outputs
+        self.current = name;
     }
 
     pub fn current_mut(&mut self) -> &mut Workspace {
-        &mut self.workspaces[self.current as usize]
+        self.workspaces
+            .iter_mut()
+            .find(|w| w.name == self.current)
+            .unwrap()
     }
 
     pub fn current(&self) -> &Workspace {
-        &self.workspaces[self.current as usize]
+        self.get(&self.current).unwrap()
     }
 
-    pub fn all_windows(&self) -> impl Iterator<Item = Ref<'_, Window>> {
+    // Like `all_windows`, without the hidden scratchpad windows
+    pub fn workspace_windows(&self) -> impl Iterator<Item = Ref<'_, Window>> {
         self.workspaces.iter().flat_map(|w| w.windows())
     }
 
+    // Includes the hidden scratchpad windows
+    pub fn all_windows(&self) -> impl Iterator<Item = Ref<'_, Window>> {
+        self.workspaces.iter().flat_map(|w| w.windows()).chain(
+            self.scratchpad
+                .iter()
+                .map(|w| Ref::map(w.borrow(), |hw| &hw.window)),
+        )
+    }
+
+    // Hides the window in the scratchpad
+    pub fn move_to_scratchpad(&mut self, window: &Window) {
+        let Some(removed) = self
+            .workspace_from_window(window)
+            .and_then(|ws| ws.remove_window(window))
+        else {
+            return;
+        };
+        removed.borrow_mut().scratchpad = true;
+        self.scratchpad.push(removed);
+        self.cleanup();
+    }
+
+    // Shows a hidden scratchpad window, the oldest one if `window` is None, floating
+    // in the middle of the current workspace
+    pub fn show_scratchpad(&mut self, window: Option<&Window>) -> Option<Window> {
+        let index = match window {
+            Some(window) => self
+                .scratchpad
+                .iter()
+                .position(|w| &w.borrow().window == window)?,
+            None if self.scratchpad.is_empty() => return None,
+            None => 0,
+        };
+        let compwindow = self.scratchpad.remove(index);
+        let workspace = self.current_mut();
+        let output_geo = workspace
+            .outputs()
+            .next()
+            .and_then(|o| workspace.output_geometry(o))
+            .unwrap_or_default();
+        {
+            let mut compwindow = compwindow.borrow_mut();
+            let size = Size::from((output_geo.size.w / 2, output_geo.size.h / 2));
+            let loc = Point::from((
+                output_geo.loc.x + (output_geo.size.w - size.w) / 2,
+                output_geo.loc.y + (output_geo.size.h - size.h) / 2,
+            ));
+            compwindow.floating = true;
+            compwindow.fullscreen = false;
+            compwindow.rec = Rectangle { loc, size };
+            compwindow.window.toplevel().with_pending_state(|state| {
+                state.states.unset(ToplevelState::TiledLeft);
+                state.states.unset(ToplevelState::TiledRight);
+                state.states.unset(ToplevelState::TiledTop);
+                state.states.unset(ToplevelState::TiledBottom);
+                state.states.unset(ToplevelState::Fullscreen);
+            });
+        }
+        let shown = compwindow.borrow().window.clone();
+        workspace.add_window(compwindow);
+        Some(shown)
+    }
+
+    // Hides the scratchpad window shown on the current workspace or shows the next one,
+    // returns the window to focus
+    pub fn toggle_scratchpad(&mut self) -> Option<Window> {
+        let shown = self
+            .current()
+            .comp_windows()
+            .find(|w| w.scratchpad)
+            .map(|w| w.window.clone());
+        match shown {
+            Some(window) => {
+                self.move_to_scratchpad(&window);
+                None
+            }
+            None => self.show_scratchpad(None),
+        }
+    }
+
+    // Like `toggle_scratchpad` for the window captured by a named scratchpad, it is pulled
+    // to the current workspace if it is shown elsewhere.
+    pub fn toggle_named_scratchpad(&mut self, name: &str) -> ScratchpadToggle {
+        let Some(window) = self.named_scratchpads.get(name).cloned() else {
+            if self.spawned_scratchpads.iter().any(|n| n == name) {
+                return ScratchpadToggle::Pending;
+            }
+            self.spawned_scratchpads.push(name.to_string());
+            return ScratchpadToggle::Spawn;
+        };
+        if self.current().contains_window(&window) {
+            self.move_to_scratchpad(&window);
+            return ScratchpadToggle::Hidden;
+        }
+        self.move_to_scratchpad(&window);
+        match self.show_scratchpad(Some(&window)) {
+            Some(window) => ScratchpadToggle::Shown(window),
+            None => ScratchpadToggle::Hidden,
+        }
+    }
+
+    // The named scratchpad that was spawned and waits for a window with this app_id
+    pub fn waiting_scratchpad(&self, app_id: Option<&str>) -> Option<String> {
+        let app_id = app_id?;
+        self.spawned_scratchpads
+            .iter()
+            .find(|name| {
+                CONFIG
+                    .scratchpads
+                    .get(*name)
+                    .is_some_and(|s| s.app_id.is_match(app_id))
+            })
+            .cloned()
+    }
+
+    // Makes a new window the window of the named scratchpad and shows it
+    pub fn capture_scratchpad(&mut self, name: &str, window: CompWindow) -> Option<Window> {
+        self.spawned_scratchpads.retain(|n| n != name);
+        self.named_scratchpads
+            .insert(name.to_string(), window.window.clone());
+        let shown = window.window.clone();
+        self.scratchpad.push(Rc::new(RefCell::new(CompWindow {
+            scratchpad: true,
+            ..window
+        })));
+        self.show_scratchpad(Some(&shown))
+    }
+
+    pub fn comp_window(&self, window: &Window) -> Option<Rc<RefCell<CompWindow>>> {
+        self.workspaces
+            .iter()
+            .flat_map(|w| w.windows.iter())
+            .chain(self.scratchpad.iter())
+            .find(|w| &w.borrow().window == window)
+            .cloned()
+    }
+
+    // Moves the mark to `window`, removing it from the window that had it before
+    pub fn mark(&mut self, window: &Window, mark: &str) {
+        for compwindow in self
+            .workspaces
+            .iter()
+            .flat_map(|w| w.windows.iter())
+            .chain(self.scratchpad.iter())
+        {
+            compwindow.borrow_mut().marks.retain(|m| m != mark);
+        }
+        if let Some(compwindow) = self.comp_window(window) {
+            compwindow.borrow_mut().marks.push(mark.to_string());
+        }
+        self.publish_marks();
+    }
+
+    pub fn marked(&self, mark: &str) -> Option<Window> {
+        self.workspaces
+            .iter()
+            .flat_map(|w| w.windows.iter())
+            .chain(self.scratchpad.iter())
+            .find(|w| w.borrow().marks.iter().any(|m| m == mark))
+            .map(|w| w.borrow().window.clone())
+    }
+
+    // Exchanges the places of two windows, even across workspaces. Every window keeps its
+    // own state, only the slots of the workspaces and their layouts are swapped.
+    pub fn swap_windows(&mut self, a: &Window, b: &Window) {
+        let (Some(first), Some(second)) = (self.comp_window(a), self.comp_window(b)) else {
+            return;
+        };
+        if Rc::ptr_eq(&first, &second) {
+            return;
+        }
+        // hidden scratchpad windows have no place to swap
+        let name_of = |window: &Window| {
+            self.workspaces
+                .iter()
+                .find(|w| w.contains_window(window))
+                .map(|w| w.name.clone())
+        };
+        let (Some(name_a), Some(name_b)) = (name_of(a), name_of(b)) else {
+            return;
+        };
+        // a tile is only swapped with a tile, a floating window with a floating one
+        if first.borrow().is_tiled() != second.borrow().is_tiled() {
+            return;
+        }
+        if !first.borrow().is_tiled() {
+            std::mem::swap(&mut first.borrow_mut().rec, &mut second.borrow_mut().rec);
+        }
+        if name_a != name_b {
+            for (window, name) in [(a, &name_a), (b, &name_b)] {
+                if let Some(output) = self.get(name).and_then(|w| w.outputs().next().cloned()) {
+                    window.output_leave(&output);
+                }
+            }
+        }
+        for name in [&name_a, &name_b] {
+            let Some(workspace) = self.get_mut(name) else {
+                continue;
+            };
+            swap_slots(&mut workspace.windows, &first, &second);
+            workspace.layout.swap_windows(&first, &second);
+            // sends the output to the windows that came in as well
+            update_layout(workspace);
+            if name_a == name_b {
+                break;
+            }
+        }
+        self.publish_marks();
+    }
+
+    // Publishes every mark with the app_id and the workspace of its window,
+    // one tab separated line per mark. Hidden scratchpad windows have no workspace.
+    pub fn publish_marks(&self) {
+        let mut lines = Vec::new();
+        for workspace in &self.workspaces {
+            for window in workspace.comp_windows() {
+                let (app_id, _) = window_identity(&window.window);
+                for mark in &window.marks {
+                    lines.push(format!(
+                        "{}\t{}\t{}",
+                        mark,
+                        app_id.as_deref().unwrap_or_default(),
+                        workspace.name
+                    ));
+                }
+            }
+        }
+        for window in &self.scratchpad {
+            let window = window.borrow();
+            let (app_id, _) = window_identity(&window.window);
+            for mark in &window.marks {
+                lines.push(format!("{}\t{}\t", mark, app_id.as_deref().unwrap_or_default()));
+            }
+        }
+        status::publish("marks", &lines.join("\n"));
+    }
+
+    // Shows `name` on `output`, the sticky windows of the workspace shown there before
+    // move along. Every change of `shown` goes through here or `remove_output`.
+    fn show_on(&mut self, output: &Output, name: &str) {
+        match self.shown.iter_mut().find(|(o, _)| o == output) {
+            Some((_, shown)) => {
+                let hidden = std::mem::replace(shown, name.to_string());
+                self.carry_sticky(&hidden, name);
+            }
+            None => self.shown.push((output.clone(), name.to_string())),
+        }
+    }
+
+    // Only floating windows can be sticky
+    pub fn toggle_sticky(&mut self, window: &Window) {
+        if let Some(compwindow) = self.comp_window(window) {
+            let mut compwindow = compwindow.borrow_mut();
+            compwindow.sticky = !compwindow.sticky && compwindow.floating;
+        }
+    }
+
+    // Moves the sticky windows of the workspace that gets hidden to the one replacing it,
+    // so every window still has exactly one workspace
+    fn carry_sticky(&mut self, from: &str, to: &str) {
+        if from == to {
+            return;
+        }
+        let Some(hidden) = self.get_mut(from) else {
+            return;
+        };
+        let sticky: Vec<Window> = hidden
+            .comp_windows()
+            .filter(|w| w.sticky)
+            .map(|w| w.window.clone())
+            .collect();
+        let moved: Vec<Rc<RefCell<CompWindow>>> = sticky
+            .iter()
+            .filter_map(|w| hidden.remove_window(w))
+            .collect();
+        if moved.is_empty() {
+            return;
+        }
+        let shown = self.get_or_create(to);
+        for window in moved {
+            shown.add_window(window);
+        }
+        self.publish_marks();
+    }
+
+    // Drops a destroyed window from the scratchpad
+    pub fn forget_scratchpad(&mut self, window: &Window) {
+        self.scratchpad.retain(|w| &w.borrow().window != window);
+        self.named_scratchpads.retain(|_, w| &*w != window);
+    }
+
     pub fn workspace_from_window(&mut self, window: &Window) -> Option<&mut Workspace> {
         self.workspaces
             .iter_mut()
             .find(|w| w.contains_window(window))
     }
 
-    pub fn activate(&mut self, id: u8) {
-        self.current = id;
+    pub fn get(&self, name: &str) -> Option<&Workspace> {
+        self.workspaces.iter().find(|w| w.name == name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Workspace> {
+        self.workspaces.iter_mut().find(|w| w.name == name)
+    }
+
+    pub fn get_or_create(&mut self, name: &str) -> &mut Workspace {
+        if self.get(name).is_none() {
+            let mut workspace = Workspace::new(name.to_string());
+            workspace.output = self.output_for(name);
+            self.workspaces.push(workspace);
+            self.workspaces.sort_by_key(|w| order_key(&w.name));
+        }
+        self.get_mut(name).unwrap()
+    }
+
+    // Destroys the workspaces that are empty, not shown and not persistent
+    pub fn cleanup(&mut self) {
+        let keep: Vec<String> = self
+            .workspaces
+            .iter()
+            .filter(|w| {
+                w.persistent || w.name == self.current || self.is_shown(&w.name) || !w.is_empty()
+            })
+            .map(|w| w.name.clone())
+            .collect();
+        self.workspaces.retain(|w| keep.contains(&w.name));
+    }
+
+    // The workspace with a loaded layout waiting for `window`, if any
+    pub fn waiting_for(&self, window: &Window) -> Option<String> {
+        self.workspaces
+            .iter()
+            .find(|w| w.layout.wants(window))
+            .map(|w| w.name.clone())
+    }
+
+    pub fn save_layouts(&self) {
+        let dir = saved_layout::session_dir();
+        // layouts of destroyed workspaces must not come back
+        for name in saved_layout::names(&dir) {
+            saved_layout::forget(&dir, &name);
+        }
+        for workspace in &self.workspaces {
+            if let Some(tree) = workspace.layout.save() {
+                if let Err(err) = saved_layout::save(&dir, &workspace.name, &tree) {
+                    eprintln!("{} Failed to save layout of workspace {}", err, workspace.name);
+                }
+            }
+        }
     }
-    pub fn move_window_to_workspace(&mut self, window: &Window, workspace: u8) {
+
+    pub fn restore_layouts(&mut self) {
+        let dir = saved_layout::session_dir();
+        for name in saved_layout::names(&dir) {
+            if let Ok(tree) = saved_layout::load(&dir, &name) {
+                self.get_or_create(&name).load_layout(tree);
+            }
+        }
+    }
+
+    pub fn activate(&mut self, name: &str) {
+        if name == self.current {
+            return;
+        }
+        // the workspace is shown on the output it is bound to, which gets the focus
+        let output = self.get_or_create(name).outputs().next().cloned();
+        if let Some(output) = output {
+            self.show_on(&output, name);
+        }
+        self.previous = Some(std::mem::replace(&mut self.current, name.to_string()));
+        self.cleanup();
+    }
+
+    // The workspace after (or before) the current one in navigation order,
+    // wrapping around at the ends if `CONFIG.workspace_wrap` is set
+    pub fn neighbour(&self, forward: bool) -> Option<String> {
+        let count = self.workspaces.len();
+        let index = self
+            .workspaces
+            .iter()
+            .position(|w| w.name == self.current)?;
+        let index = match (forward, CONFIG.workspace_wrap) {
+            (true, _) if index + 1 < count => index + 1,
+            (true, true) => 0,
+            (false, _) if index > 0 => index - 1,
+            (false, true) => count - 1,
+            _ => return None,
+        };
+        Some(self.workspaces[index].name.clone()).filter(|name| name != &self.current)
+    }
+
+    pub fn move_window_to_workspace(&mut self, window: &Window, workspace: &str) {
         let mut removed = None;
         if let Some(ws) = self.workspace_from_window(window) {
             removed = ws.remove_window(window);
-            bsp_update_layout(ws)
         }
         if let Some(removed) = removed {
-            self.workspaces[workspace as usize].add_window(removed);
-            bsp_update_layout(&mut self.workspaces[workspace as usize])
+            self.get_or_create(workspace).add_window(removed);
         }
+        self.cleanup();
+        self.publish_marks();
     }
 }